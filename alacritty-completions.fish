@@ -33,6 +33,10 @@ complete \
   -c alacritty \
   -l "print-events" \
   -d "Print all events to stdout"
+complete \
+  -c alacritty \
+  -l "print-keys" \
+  -d "Print the name and scancode of each pressed key"
 complete \
   -c alacritty \
   -s "q" \
@@ -70,3 +74,18 @@ complete \
   -s "e" \
   -l "command" \
   -d "Execute command (must be last arg)"
+
+# Keyboard macros
+complete \
+  -c alacritty \
+  -n "__fish_use_subcommand" \
+  -f \
+  -a "replay" \
+  -d "Replay a recorded keyboard macro"
+complete \
+  -c alacritty \
+  -n "__fish_seen_subcommand_from replay" \
+  -x \
+  -s "n" \
+  -l "count" \
+  -d "Number of times to replay the macro"
@@ -19,7 +19,7 @@ use std::str;
 
 use vte;
 use base64;
-use index::{Column, Line, Contains};
+use index::{Column, Line, Point, Contains};
 
 use ::{MouseCursor, Rgb};
 
@@ -78,6 +78,38 @@ fn parse_rgb_color(color: &[u8]) -> Option<Rgb> {
     }
 }
 
+// Parse the path of a `file://host/path` URL, as sent by OSC 7
+fn parse_file_url(url: &[u8]) -> Option<String> {
+    if !url.starts_with(b"file://") {
+        return None;
+    }
+
+    // The host is left out since it's only used to detect remote shells
+    let url = &url[7..];
+    let path = match url.iter().position(|&b| b == b'/') {
+        Some(start) => &url[start..],
+        None => return None,
+    };
+
+    let mut decoded = Vec::with_capacity(path.len());
+    let mut bytes = path.iter();
+    while let Some(&byte) = bytes.next() {
+        if byte != b'%' {
+            decoded.push(byte);
+            continue;
+        }
+
+        let high = bytes.next().and_then(|&b| (b as char).to_digit(16));
+        let low = bytes.next().and_then(|&b| (b as char).to_digit(16));
+        match (high, low) {
+            (Some(high), Some(low)) => decoded.push((high << 4 | low) as u8),
+            _ => return None,
+        }
+    }
+
+    String::from_utf8(decoded).ok()
+}
+
 fn parse_number(input: &[u8]) -> Option<u8> {
     if input.is_empty() {
         return None;
@@ -105,7 +137,60 @@ pub struct Processor {
 
 /// Internal state for VTE processor
 struct ProcessorState {
-    preceding_char: Option<char>
+    preceding_char: Option<char>,
+
+    /// Continuation bytes still expected by the current UTF-8 sequence
+    utf8_remaining: u8,
+
+    /// Pending VT52 direct cursor address
+    vt52_address: Option<Vt52Address>,
+
+    /// Printer controller mode, with the bytes which may start `CSI 4 i`
+    printer_controller: Option<Vec<u8>>,
+}
+
+/// Sequences leaving printer controller mode
+const PRINTER_CONTROLLER_OFF: [&[u8]; 2] = [b"\x1b[4i", b"\x9b4i"];
+
+/// Progress of the VT52 direct cursor address `ESC Y line column`
+///
+/// Line and column are sent as single characters offset by 0x20.
+#[derive(Clone, Copy, Debug)]
+enum Vt52Address {
+    Line,
+    Column(Line),
+}
+
+impl ProcessorState {
+    /// Check whether `byte` is a raw 8-bit C1 control
+    ///
+    /// Bytes in the C1 range are also valid UTF-8 continuation bytes, so this
+    /// keeps track of multi-byte sequences to tell the two apart.
+    fn is_c1_control(&mut self, byte: u8) -> bool {
+        match byte {
+            0x80...0xbf if self.utf8_remaining > 0 => {
+                self.utf8_remaining -= 1;
+                false
+            },
+            0x80...0x9f => true,
+            0xc2...0xdf => {
+                self.utf8_remaining = 1;
+                false
+            },
+            0xe0...0xef => {
+                self.utf8_remaining = 2;
+                false
+            },
+            0xf0...0xf4 => {
+                self.utf8_remaining = 3;
+                false
+            },
+            _ => {
+                self.utf8_remaining = 0;
+                false
+            },
+        }
+    }
 }
 
 /// Helper type that implements `vte::Perform`.
@@ -132,12 +217,48 @@ impl<'a, H: Handler + TermInfo + 'a, W: io::Write> Performer<'a, H, W> {
             writer,
         }
     }
+
+    /// Dispatch an escape sequence in VT52 mode
+    #[inline]
+    fn vt52_esc_dispatch(&mut self, intermediates: &[u8], byte: u8) {
+        // An escape sequence cancels an incomplete cursor address
+        self._state.vt52_address = None;
+
+        if !intermediates.is_empty() {
+            debug!("[unhandled] vt52 esc_dispatch ints={:?}, byte={:?}", intermediates, byte);
+            return;
+        }
+
+        match byte {
+            b'A' => self.handler.move_up(Line(1)),
+            b'B' => self.handler.move_down(Line(1)),
+            b'C' => self.handler.move_forward(Column(1)),
+            b'D' => self.handler.move_backward(Column(1)),
+            b'F' => self.handler.set_vt52_graphics(true),
+            b'G' => self.handler.set_vt52_graphics(false),
+            b'H' => self.handler.goto(Line(0), Column(0)),
+            b'I' => self.handler.reverse_index(),
+            b'J' => self.handler.clear_screen(ClearMode::Below),
+            b'K' => self.handler.clear_line(LineClearMode::Right),
+            b'Y' => self._state.vt52_address = Some(Vt52Address::Line),
+            b'Z' => self.handler.identify_terminal(self.writer),
+            b'=' => self.handler.set_keypad_application_mode(),
+            b'>' => self.handler.unset_keypad_application_mode(),
+            b'<' => self.handler.set_mode(Mode::Ansi),
+            _ => debug!("[unhandled] vt52 esc_dispatch byte={:?} ({:02x})", byte as char, byte),
+        }
+    }
 }
 
 impl Default for Processor {
     fn default() -> Processor {
         Processor {
-            state: ProcessorState { preceding_char: None },
+            state: ProcessorState {
+                preceding_char: None,
+                utf8_remaining: 0,
+                vt52_address: None,
+                printer_controller: None,
+            },
             parser: vte::Parser::new(),
         }
     }
@@ -158,9 +279,55 @@ impl Processor {
         where H: Handler + TermInfo,
               W: io::Write
     {
+        if self.state.printer_controller.is_some() {
+            return self.advance_printer_controller(handler, byte);
+        }
+
+        // Raw C1 controls are either dropped or replaced by their 7-bit
+        // equivalent, ESC followed by a byte in the range 0x40 to 0x5f.
+        if self.state.is_c1_control(byte) {
+            if !handler.c1_controls() {
+                debug!("[ignored] 8-bit control byte={:02x}", byte);
+                return;
+            }
+
+            let mut performer = Performer::new(&mut self.state, handler, writer);
+            self.parser.advance(&mut performer, C0::ESC);
+            self.parser.advance(&mut performer, byte - 0x40);
+            return;
+        }
+
         let mut performer = Performer::new(&mut self.state, handler, writer);
         self.parser.advance(&mut performer, byte);
     }
+
+    /// Pass bytes to the printer until printer controller mode is turned off
+    fn advance_printer_controller<H: Handler>(&mut self, handler: &mut H, byte: u8) {
+        let mut pending = self.state.printer_controller.take().unwrap_or_default();
+        pending.push(byte);
+
+        if PRINTER_CONTROLLER_OFF.iter().any(|off| *off == &pending[..]) {
+            handler.stop_printer_controller();
+            return;
+        }
+
+        if !PRINTER_CONTROLLER_OFF.iter().any(|off| off.starts_with(&pending)) {
+            // The last byte may still start the sequence turning the printer off
+            let last = pending.pop().unwrap();
+            if !pending.is_empty() {
+                handler.printer_output(&pending);
+                pending.clear();
+            }
+
+            if PRINTER_CONTROLLER_OFF.iter().any(|off| off[0] == last) {
+                pending.push(last);
+            } else {
+                handler.printer_output(&[last]);
+            }
+        }
+
+        self.state.printer_controller = Some(pending);
+    }
 }
 
 
@@ -168,6 +335,16 @@ impl Processor {
 pub trait TermInfo {
     fn lines(&self) -> Line;
     fn cols(&self) -> Column;
+
+    /// Whether escape sequences are interpreted like a VT52
+    fn vt52_mode(&self) -> bool {
+        false
+    }
+
+    /// Whether raw 8-bit C1 controls are interpreted or ignored
+    fn c1_controls(&self) -> bool {
+        true
+    }
 }
 
 /// Type that handles actions from the parser
@@ -178,6 +355,9 @@ pub trait Handler {
     /// OSC to set window title
     fn set_title(&mut self, &str) {}
 
+    /// OSC 7 to report the working directory of the shell
+    fn set_working_directory(&mut self, &str) {}
+
     /// Set the window's mouse cursor
     fn set_mouse_cursor(&mut self, MouseCursor) {}
 
@@ -210,6 +390,30 @@ pub trait Handler {
     /// TODO this should probably return an io::Result
     fn identify_terminal<W: io::Write>(&mut self, &mut W) {}
 
+    /// Report the terminal type and version (DA2)
+    fn secondary_device_attributes<W: io::Write>(&mut self, &mut W) {}
+
+    /// Report the terminal unit ID (DA3)
+    fn tertiary_device_attributes<W: io::Write>(&mut self, &mut W) {}
+
+    /// Report the terminal name and version (XTVERSION)
+    fn report_version<W: io::Write>(&mut self, &mut W) {}
+
+    /// Print the text of the screen (MC 0)
+    fn print_screen(&mut self) {}
+
+    /// Start passing data to the printer without displaying it (MC 5)
+    fn start_printer_controller(&mut self) {}
+
+    /// Data received in printer controller mode
+    fn printer_output(&mut self, &[u8]) {}
+
+    /// Leave printer controller mode (MC 4)
+    fn stop_printer_controller(&mut self) {}
+
+    /// Select how C1 controls are sent in replies (S7C1T and S8C1T)
+    fn set_c1_encoding(&mut self, C1Encoding) {}
+
     // Report device status
     fn device_status<W: io::Write>(&mut self, &mut W, usize) {}
 
@@ -293,12 +497,30 @@ pub trait Handler {
     /// Clear screen
     fn clear_screen(&mut self, _mode: ClearMode) {}
 
+    /// DECSEL - Clear current line, skipping protected cells
+    fn selective_clear_line(&mut self, _mode: LineClearMode) {}
+
+    /// DECSED - Clear screen, skipping protected cells
+    fn selective_clear_screen(&mut self, _mode: ClearMode) {}
+
+    /// DECSCA - Set whether following characters are protected from selective erase
+    fn set_character_protection(&mut self, _protected: bool) {}
+
     /// Clear tab stops
     fn clear_tabs(&mut self, _mode: TabulationClearMode) {}
 
+    /// Select the type of status line (DECSSDT)
+    fn set_status_line_type(&mut self, StatusLineType) {}
+
+    /// Direct output to the main display or the status line (DECSASD)
+    fn select_active_display(&mut self, StatusDisplay) {}
+
     /// Reset terminal state
     fn reset_state(&mut self) {}
 
+    /// DECSTR - Reset modes, attributes and character sets, keeping the screen
+    fn soft_reset(&mut self) {}
+
     /// Reverse Index
     ///
     /// Move the active position to the same horizontal position on the
@@ -330,23 +552,52 @@ pub trait Handler {
     /// shift out and locking shift depending on the set being activated
     fn set_active_charset(&mut self, CharsetIndex) {}
 
+    /// Invoke G2 or G3 in the GL area for the next graphic character only
+    ///
+    /// Also referred to as single shift two and single shift three
+    fn single_shift(&mut self, CharsetIndex) {}
+
     /// Assign a graphic character set to G0, G1, G2 or G3
     ///
     /// 'Designate' a graphic character set as one of G0 to G3, so that it can
     /// later be 'invoked' by `set_active_charset`
     fn configure_charset(&mut self, CharsetIndex, StandardCharset) {}
 
+    /// Enter or leave the graphics mode of a VT52, `ESC F` and `ESC G`
+    ///
+    /// The special graphics replace ASCII independently of the designated
+    /// character sets, which are kept for when ANSI mode is entered again.
+    fn set_vt52_graphics(&mut self, bool) {}
+
     /// Set an indexed color value
     fn set_color(&mut self, usize, Rgb) {}
 
     /// Reset an indexed color to original value
     fn reset_color(&mut self, usize) {}
 
+    /// Report the value of an indexed color
+    fn color_report<W: io::Write>(&mut self, &mut W, usize) {}
+
     /// Set the clipboard
     fn set_clipboard(&mut self, &str) {}
 
     /// Run the dectest routine
     fn dectest(&mut self) {}
+
+    /// DECCRA - Copy a rectangular area to `destination`
+    fn copy_rectangle(&mut self, _area: Rectangle, _destination: Point) {}
+
+    /// DECFRA - Fill a rectangular area with `c`
+    fn fill_rectangle(&mut self, _c: char, _area: Rectangle) {}
+
+    /// DECERA - Erase a rectangular area
+    fn erase_rectangle(&mut self, _area: Rectangle) {}
+
+    /// DECCARA - Change an attribute of all cells in a rectangular area
+    fn change_rectangle_attribute(&mut self, _area: Rectangle, _attr: Attr) {}
+
+    /// DECRARA - Reverse an attribute of all cells in a rectangular area
+    fn reverse_rectangle_attribute(&mut self, _area: Rectangle, _attr: Attr) {}
 }
 
 /// Describes shape of cursor
@@ -376,6 +627,11 @@ impl Default for CursorStyle {
 pub enum Mode {
     /// ?1
     CursorKeys = 1,
+    /// ?2 DECANM ANSI/VT52 Mode
+    ///
+    /// * `CSI ? 2 h` ANSI mode, the default
+    /// * `CSI ? 2 l` VT52 mode, left with `ESC <`
+    Ansi = 2,
     /// Select 80 or 132 columns per page
     ///
     /// CSI ? 3 h -> set 132 column font
@@ -395,6 +651,13 @@ pub enum Mode {
     /// * `CSI 4 h` change to insert mode
     /// * `CSI 4 l` reset to replacement mode
     Insert = 4,
+    /// BDSM Bi-directional Support Mode
+    ///
+    /// * `CSI 8 h` explicit mode, the application reorders bidi text itself
+    /// * `CSI 8 l` implicit mode, the terminal reorders bidi text for display
+    ExplicitBidi = 8,
+    /// ?5
+    ReverseVideo = 5,
     /// ?6
     Origin = 6,
     /// ?7
@@ -418,6 +681,12 @@ pub enum Mode {
     ReportFocusInOut = 1004,
     /// ?1006
     SgrMouse = 1006,
+    /// ?47
+    SwapScreen = 47,
+    /// ?1047
+    SwapScreenAndClear = 1047,
+    /// ?1048
+    SetRestoreCursor = 1048,
     /// ?1049
     SwapScreenAndSetRestoreCursor = 1049,
     /// ?2004
@@ -432,16 +701,21 @@ impl Mode {
         if private {
             Some(match num {
                 1 => Mode::CursorKeys,
+                2 => Mode::Ansi,
                 3 => Mode::DECCOLM,
+                5 => Mode::ReverseVideo,
                 6 => Mode::Origin,
                 7 => Mode::LineWrap,
                 12 => Mode::BlinkingCursor,
                 25 => Mode::ShowCursor,
+                47 => Mode::SwapScreen,
                 1000 => Mode::ReportMouseClicks,
                 1002 => Mode::ReportCellMouseMotion,
                 1003 => Mode::ReportAllMouseMotion,
                 1004 => Mode::ReportFocusInOut,
                 1006 => Mode::SgrMouse,
+                1047 => Mode::SwapScreenAndClear,
+                1048 => Mode::SetRestoreCursor,
                 1049 => Mode::SwapScreenAndSetRestoreCursor,
                 2004 => Mode::BracketedPaste,
                 _ => {
@@ -452,6 +726,7 @@ impl Mode {
         } else {
             Some(match num {
                 4 => Mode::Insert,
+                8 => Mode::ExplicitBidi,
                 20 => Mode::LineFeedNewLine,
                 _ => return None
             })
@@ -496,6 +771,45 @@ pub enum TabulationClearMode {
     All,
 }
 
+/// Type of the status line, selected with DECSSDT
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum StatusLineType {
+    /// No status line
+    None,
+    /// Status line showing the cursor position
+    Indicator,
+    /// Status line written by the application
+    HostWritable,
+}
+
+impl Default for StatusLineType {
+    fn default() -> Self {
+        StatusLineType::None
+    }
+}
+
+/// Display receiving output, selected with DECSASD
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum StatusDisplay {
+    /// Main display
+    Main,
+    /// Host-writable status line
+    StatusLine,
+}
+
+/// Rectangular area of the screen
+///
+/// Used by the DEC rectangular area operations (DECCRA, DECFRA, DECERA, DECCARA
+/// and DECRARA). Both corners are inclusive and relative to the origin, so the
+/// handler is responsible for applying origin mode.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Rectangle {
+    /// Top left corner
+    pub start: Point,
+    /// Bottom right corner
+    pub end: Point,
+}
+
 /// Standard colors
 ///
 /// The order here matters since the enum should be castable to a `usize` for
@@ -658,6 +972,34 @@ pub enum Attr {
     Background(Color),
 }
 
+/// Encoding of the C1 controls sent back to the application
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum C1Encoding {
+    /// ESC followed by a byte in the range 0x40 to 0x5f
+    SevenBit,
+    /// Single UTF-8 encoded character in the range U+0080 to U+009F
+    EightBit,
+}
+
+impl Default for C1Encoding {
+    fn default() -> Self {
+        C1Encoding::SevenBit
+    }
+}
+
+impl C1Encoding {
+    /// Write the C1 control `byte`, for example `C1::CSI`
+    pub fn write_control<W: io::Write>(self, writer: &mut W, byte: u8) -> io::Result<()> {
+        match self {
+            C1Encoding::SevenBit => writer.write_all(&[C0::ESC, byte - 0x40]),
+            C1Encoding::EightBit => {
+                let mut buf = [0; 4];
+                writer.write_all(char::from(byte).encode_utf8(&mut buf).as_bytes())
+            },
+        }
+    }
+}
+
 /// Identifiers which can be assigned to a graphic character set
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum CharsetIndex {
@@ -679,6 +1021,54 @@ impl Default for CharsetIndex {
 pub enum StandardCharset {
     Ascii,
     SpecialCharacterAndLineDrawing,
+    /// DEC Supplemental Graphic, mostly the upper half of ISO Latin-1
+    DecSupplemental,
+    /// DEC Technical, mathematical symbols and greek letters
+    DecTechnical,
+    /// United Kingdom, ASCII with `#` replaced by `£`
+    British,
+
+    // National replacement character sets, which replace some of the ASCII
+    // punctuation with letters of the national alphabet
+    Dutch,
+    Finnish,
+    French,
+    FrenchCanadian,
+    German,
+    Italian,
+    NorwegianDanish,
+    Portuguese,
+    Spanish,
+    Swedish,
+    Swiss,
+}
+
+impl StandardCharset {
+    /// Find the character set designated by the final byte of an escape
+    /// sequence
+    ///
+    /// The VT300 sets are selected with an additional `%` intermediate.
+    pub fn from_designator(intermediate: Option<u8>, byte: u8) -> Option<StandardCharset> {
+        Some(match (intermediate, byte) {
+            (None, b'B') => StandardCharset::Ascii,
+            (None, b'0') => StandardCharset::SpecialCharacterAndLineDrawing,
+            (None, b'<') | (Some(b'%'), b'5') => StandardCharset::DecSupplemental,
+            (None, b'>') => StandardCharset::DecTechnical,
+            (None, b'A') => StandardCharset::British,
+            (None, b'4') => StandardCharset::Dutch,
+            (None, b'C') | (None, b'5') => StandardCharset::Finnish,
+            (None, b'R') | (None, b'f') => StandardCharset::French,
+            (None, b'Q') | (None, b'9') => StandardCharset::FrenchCanadian,
+            (None, b'K') => StandardCharset::German,
+            (None, b'Y') => StandardCharset::Italian,
+            (None, b'E') | (None, b'6') | (None, b'`') => StandardCharset::NorwegianDanish,
+            (Some(b'%'), b'6') => StandardCharset::Portuguese,
+            (None, b'Z') => StandardCharset::Spanish,
+            (None, b'H') | (None, b'7') => StandardCharset::Swedish,
+            (None, b'=') => StandardCharset::Swiss,
+            _ => return None,
+        })
+    }
 }
 
 impl Default for StandardCharset {
@@ -693,6 +1083,18 @@ impl<'a, H, W> vte::Perform for Performer<'a, H, W>
 {
     #[inline]
     fn print(&mut self, c: char) {
+        // Characters following VT52 `ESC Y` are the cursor address
+        if let Some(address) = self._state.vt52_address.take() {
+            let position = (c as usize).saturating_sub(0x20);
+            match address {
+                Vt52Address::Line => {
+                    self._state.vt52_address = Some(Vt52Address::Column(Line(position)));
+                },
+                Vt52Address::Column(line) => self.handler.goto(line, Column(position)),
+            }
+            return;
+        }
+
         self.handler.input(c);
         self._state.preceding_char = Some(c);
     }
@@ -708,9 +1110,6 @@ impl<'a, H, W> vte::Perform for Performer<'a, H, W>
             C0::SUB => self.handler.substitute(),
             C0::SI => self.handler.set_active_charset(CharsetIndex::G0),
             C0::SO => self.handler.set_active_charset(CharsetIndex::G1),
-            C1::NEL => self.handler.newline(),
-            C1::HTS => self.handler.set_horizontal_tabstop(),
-            C1::DECID => self.handler.identify_terminal(self.writer),
             _ => debug!("[unhandled] execute byte={:02x}", byte)
         }
     }
@@ -766,14 +1165,30 @@ impl<'a, H, W> vte::Perform for Performer<'a, H, W>
             // This is ignored, since alacritty has no concept of tabs
             b"1" => return,
 
+            // Set working directory
+            b"7" => {
+                // Semicolons in the URL were taken as parameter separators
+                let url = params[1..].join(&b';');
+                match parse_file_url(&url) {
+                    Some(path) => self.handler.set_working_directory(&path),
+                    None => unhandled(params),
+                }
+            },
+
             // Set color index
             b"4" => {
                 if params.len() > 1 && params.len() % 2 != 0 {
                     for chunk in params[1..].chunks(2) {
-                        let index = parse_number(chunk[0]);
-                        let color = parse_rgb_color(chunk[1]);
-                        if let (Some(i), Some(c)) = (index, color) {
-                            self.handler.set_color(i as usize, c);
+                        let index = match parse_number(chunk[0]) {
+                            Some(index) => index as usize,
+                            None => continue,
+                        };
+                        if chunk[1] == b"?" {
+                            self.handler.color_report(self.writer, index);
+                            return;
+                        }
+                        if let Some(color) = parse_rgb_color(chunk[1]) {
+                            self.handler.set_color(index, color);
                             return;
                         }
                     }
@@ -784,6 +1199,10 @@ impl<'a, H, W> vte::Perform for Performer<'a, H, W>
             // Set foreground color
             b"10" => {
                 if params.len() >= 2 {
+                    if params[1] == b"?" {
+                        self.handler.color_report(self.writer, NamedColor::Foreground as usize);
+                        return;
+                    }
                     if let Some(color) = parse_rgb_color(params[1]) {
                         self.handler.set_color(NamedColor::Foreground as usize, color);
                         return;
@@ -795,6 +1214,10 @@ impl<'a, H, W> vte::Perform for Performer<'a, H, W>
             // Set background color
             b"11" => {
                 if params.len() >= 2 {
+                    if params[1] == b"?" {
+                        self.handler.color_report(self.writer, NamedColor::Background as usize);
+                        return;
+                    }
                     if let Some(color) = parse_rgb_color(params[1]) {
                         self.handler.set_color(NamedColor::Background as usize, color);
                         return;
@@ -806,6 +1229,10 @@ impl<'a, H, W> vte::Perform for Performer<'a, H, W>
             // Set text cursor color
             b"12" => {
                 if params.len() >= 2 {
+                    if params[1] == b"?" {
+                        self.handler.color_report(self.writer, NamedColor::Cursor as usize);
+                        return;
+                    }
                     if let Some(color) = parse_rgb_color(params[1]) {
                         self.handler.set_color(NamedColor::Cursor as usize, color);
                         return;
@@ -872,7 +1299,14 @@ impl<'a, H, W> vte::Perform for Performer<'a, H, W>
         _ignore: bool,
         action: char
     ) {
+        // A VT52 has no control sequences
+        if self.handler.vt52_mode() {
+            debug!("[ignored] CSI in VT52 mode action={:?}, args={:?}", action, args);
+            return;
+        }
+
         let private = intermediates.get(0).map(|b| *b == b'?').unwrap_or(false);
+        let intermediate = intermediates.get(0).cloned();
         let handler = &mut self.handler;
         let writer = &mut self.writer;
 
@@ -896,6 +1330,20 @@ impl<'a, H, W> vte::Perform for Performer<'a, H, W>
             }
         }
 
+        macro_rules! rectangle {
+            (idx: $idx:expr) => {{
+                let top = arg_or_default!(idx: $idx, default: 1) as usize;
+                let left = arg_or_default!(idx: $idx + 1, default: 1) as usize;
+                let bottom = arg_or_default!(idx: $idx + 2, default: handler.lines().0 as _) as usize;
+                let right = arg_or_default!(idx: $idx + 3, default: handler.cols().0 as _) as usize;
+
+                Rectangle {
+                    start: Point::new(Line(top - 1), Column(left - 1)),
+                    end: Point::new(Line(bottom - 1), Column(right - 1)),
+                }
+            }}
+        }
+
         match action {
             '@' => handler.insert_blank(Column(arg_or_default!(idx: 0, default: 1) as usize)),
             'A' => {
@@ -912,6 +1360,8 @@ impl<'a, H, W> vte::Perform for Performer<'a, H, W>
                 }
             },
             'B' | 'e' => handler.move_down(Line(arg_or_default!(idx: 0, default: 1) as usize)),
+            'c' if intermediate == Some(b'>') => handler.secondary_device_attributes(writer),
+            'c' if intermediate == Some(b'=') => handler.tertiary_device_attributes(writer),
             'c' => handler.identify_terminal(writer),
             'C' | 'a' => handler.move_forward(Column(arg_or_default!(idx: 0, default: 1) as usize)),
             'D' => handler.move_backward(Column(arg_or_default!(idx: 0, default: 1) as usize)),
@@ -933,6 +1383,14 @@ impl<'a, H, W> vte::Perform for Performer<'a, H, W>
                 handler.goto(Line(y - 1), Column(x - 1));
             },
             'I' => handler.move_forward_tabs(arg_or_default!(idx: 0, default: 1)),
+            'i' if intermediate.is_none() => match arg_or_default!(idx: 0, default: 0) {
+                0 => handler.print_screen(),
+                5 => {
+                    self._state.printer_controller = Some(Vec::new());
+                    handler.start_printer_controller();
+                },
+                _ => unhandled!(),
+            },
             'J' => {
                 let mode = match arg_or_default!(idx: 0, default: 0) {
                     0 => ClearMode::Below,
@@ -942,7 +1400,11 @@ impl<'a, H, W> vte::Perform for Performer<'a, H, W>
                     _ => unhandled!(),
                 };
 
-                handler.clear_screen(mode);
+                if private {
+                    handler.selective_clear_screen(mode);
+                } else {
+                    handler.clear_screen(mode);
+                }
             },
             'K' => {
                 let mode = match arg_or_default!(idx: 0, default: 0) {
@@ -952,7 +1414,11 @@ impl<'a, H, W> vte::Perform for Performer<'a, H, W>
                     _ => unhandled!(),
                 };
 
-                handler.clear_line(mode);
+                if private {
+                    handler.selective_clear_line(mode);
+                } else {
+                    handler.clear_line(mode);
+                }
             },
             'S' => handler.scroll_up(Line(arg_or_default!(idx: 0, default: 1) as usize)),
             'T' => handler.scroll_down(Line(arg_or_default!(idx: 0, default: 1) as usize)),
@@ -970,6 +1436,25 @@ impl<'a, H, W> vte::Perform for Performer<'a, H, W>
             'X' => handler.erase_chars(Column(arg_or_default!(idx: 0, default: 1) as usize)),
             'P' => handler.delete_chars(Column(arg_or_default!(idx: 0, default: 1) as usize)),
             'Z' => handler.move_backward_tabs(arg_or_default!(idx: 0, default: 1)),
+            '~' if intermediate == Some(b'$') => {
+                let kind = match arg_or_default!(idx: 0, default: 0) {
+                    0 => StatusLineType::None,
+                    1 => StatusLineType::Indicator,
+                    2 => StatusLineType::HostWritable,
+                    _ => unhandled!(),
+                };
+
+                handler.set_status_line_type(kind);
+            },
+            '}' if intermediate == Some(b'$') => {
+                let display = match arg_or_default!(idx: 0, default: 0) {
+                    0 => StatusDisplay::Main,
+                    1 => StatusDisplay::StatusLine,
+                    _ => unhandled!(),
+                };
+
+                handler.select_active_display(display);
+            },
             'd' => handler.goto_line(Line(arg_or_default!(idx: 0, default: 1) as usize - 1)),
             'h' => {
                 for arg in args {
@@ -1072,6 +1557,29 @@ impl<'a, H, W> vte::Perform for Performer<'a, H, W>
                 }
             }
             'n' => handler.device_status(writer, arg_or_default!(idx: 0, default: 0) as usize),
+            'r' if intermediate == Some(b'$') => {
+                let area = rectangle!(idx: 0);
+                if args.len() <= 4 {
+                    handler.change_rectangle_attribute(area, Attr::Reset);
+                }
+
+                for arg in args.iter().skip(4) {
+                    let attr = match *arg {
+                        0 => Attr::Reset,
+                        1 => Attr::Bold,
+                        4 => Attr::Underscore,
+                        5 => Attr::BlinkSlow,
+                        7 => Attr::Reverse,
+                        22 => Attr::CancelBold,
+                        24 => Attr::CancelUnderline,
+                        25 => Attr::CancelBlink,
+                        27 => Attr::CancelReverse,
+                        _ => unhandled!(),
+                    };
+
+                    handler.change_rectangle_attribute(area, attr);
+                }
+            },
             'r' => {
                 if private {
                     unhandled!();
@@ -1088,7 +1596,53 @@ impl<'a, H, W> vte::Perform for Performer<'a, H, W>
                 handler.set_scrolling_region(top..bottom);
             },
             's' => handler.save_cursor_position(),
+            't' if intermediate == Some(b'$') => {
+                let area = rectangle!(idx: 0);
+                if args.len() <= 4 {
+                    handler.reverse_rectangle_attribute(area, Attr::Reset);
+                }
+
+                for arg in args.iter().skip(4) {
+                    let attr = match *arg {
+                        0 => Attr::Reset,
+                        1 => Attr::Bold,
+                        4 => Attr::Underscore,
+                        5 => Attr::BlinkSlow,
+                        7 => Attr::Reverse,
+                        _ => unhandled!(),
+                    };
+
+                    handler.reverse_rectangle_attribute(area, attr);
+                }
+            },
             'u' => handler.restore_cursor_position(),
+            'v' if intermediate == Some(b'$') => {
+                let area = rectangle!(idx: 0);
+                let line = arg_or_default!(idx: 5, default: 1) as usize;
+                let col = arg_or_default!(idx: 6, default: 1) as usize;
+
+                handler.copy_rectangle(area, Point::new(Line(line - 1), Column(col - 1)));
+            },
+            'x' if intermediate == Some(b'$') => {
+                let c = match args.get(0).and_then(|c| ::std::char::from_u32(*c as u32)) {
+                    Some(c @ ' ' ... '~') | Some(c @ '\u{a0}' ... '\u{ff}') => c,
+                    _ => unhandled!(),
+                };
+
+                handler.fill_rectangle(c, rectangle!(idx: 1));
+            },
+            'z' if intermediate == Some(b'$') => handler.erase_rectangle(rectangle!(idx: 0)),
+            'p' if intermediate == Some(b'!') => handler.soft_reset(),
+            'q' if intermediate == Some(b'>') => handler.report_version(writer),
+            'q' if intermediate == Some(b'"') => {
+                let protected = match arg_or_default!(idx: 0, default: 0) {
+                    0 | 2 => false,
+                    1 => true,
+                    _ => unhandled!(),
+                };
+
+                handler.set_character_protection(protected);
+            },
             'q' => {
                 let style = match arg_or_default!(idx: 0, default: 0) {
                     0 => None,
@@ -1120,21 +1674,34 @@ impl<'a, H, W> vte::Perform for Performer<'a, H, W>
             }}
         }
 
-        macro_rules! configure_charset {
-            ($charset:path) => {{
-                let index: CharsetIndex = match intermediates.first().cloned() {
-                    Some(b'(') => CharsetIndex::G0,
-                    Some(b')') => CharsetIndex::G1,
-                    Some(b'*') => CharsetIndex::G2,
-                    Some(b'+') => CharsetIndex::G3,
-                    _ => unhandled!(),
-                };
-                self.handler.configure_charset(index, $charset)
-            }}
+        if self.handler.vt52_mode() {
+            return self.vt52_esc_dispatch(intermediates, byte);
+        }
+
+        // Designate a character set as one of G0 to G3
+        let index = match intermediates.first().cloned() {
+            Some(b'(') => Some(CharsetIndex::G0),
+            Some(b')') => Some(CharsetIndex::G1),
+            Some(b'*') => Some(CharsetIndex::G2),
+            Some(b'+') => Some(CharsetIndex::G3),
+            _ => None,
+        };
+
+        if let Some(index) = index {
+            match StandardCharset::from_designator(intermediates.get(1).cloned(), byte) {
+                Some(charset) => self.handler.configure_charset(index, charset),
+                None => unhandled!(),
+            }
+            return;
         }
 
         match byte {
-            b'B' => configure_charset!(StandardCharset::Ascii),
+            b'F' if intermediates == [b' '] => {
+                self.handler.set_c1_encoding(C1Encoding::SevenBit)
+            },
+            b'G' if intermediates == [b' '] => {
+                self.handler.set_c1_encoding(C1Encoding::EightBit)
+            },
             b'D' => self.handler.linefeed(),
             b'E' => {
                 self.handler.linefeed();
@@ -1142,9 +1709,12 @@ impl<'a, H, W> vte::Perform for Performer<'a, H, W>
             }
             b'H' => self.handler.set_horizontal_tabstop(),
             b'M' => self.handler.reverse_index(),
+            b'N' => self.handler.single_shift(CharsetIndex::G2),
+            b'O' => self.handler.single_shift(CharsetIndex::G3),
             b'Z' => self.handler.identify_terminal(self.writer),
             b'c' => self.handler.reset_state(),
-            b'0' => configure_charset!(StandardCharset::SpecialCharacterAndLineDrawing),
+            b'n' => self.handler.set_active_charset(CharsetIndex::G2),
+            b'o' => self.handler.set_active_charset(CharsetIndex::G3),
             b'7' => self.handler.save_cursor_position(),
             b'8' => {
                 if !intermediates.is_empty() && intermediates[0] == b'#' {
@@ -1372,6 +1942,7 @@ mod tests {
     use std::io;
     use index::{Line, Column};
     use super::{Processor, Handler, Attr, TermInfo, Color, StandardCharset, CharsetIndex, parse_rgb_color, parse_number};
+    use super::{C1Encoding, NamedColor, parse_file_url};
     use ::Rgb;
 
     /// The /dev/null of `io::Write`
@@ -1480,6 +2051,7 @@ mod tests {
     struct CharsetHandler {
         index: CharsetIndex,
         charset: StandardCharset,
+        single_shift: Option<CharsetIndex>,
     }
 
     impl Default for CharsetHandler {
@@ -1487,6 +2059,7 @@ mod tests {
             CharsetHandler {
                 index: CharsetIndex::G0,
                 charset: StandardCharset::Ascii,
+                single_shift: None,
             }
         }
     }
@@ -1500,6 +2073,10 @@ mod tests {
         fn set_active_charset(&mut self, index: CharsetIndex) {
             self.index = index;
         }
+
+        fn single_shift(&mut self, index: CharsetIndex) {
+            self.single_shift = Some(index);
+        }
     }
 
     impl TermInfo for CharsetHandler {
@@ -1540,6 +2117,201 @@ mod tests {
         assert_eq!(handler.index, CharsetIndex::G1);
     }
 
+    #[test]
+    fn parse_designate_national_charsets() {
+        let mut parser = Processor::new();
+        let mut handler = CharsetHandler::default();
+
+        for byte in b"\x1b*K" {
+            parser.advance(&mut handler, *byte, &mut Void);
+        }
+
+        assert_eq!(handler.index, CharsetIndex::G2);
+        assert_eq!(handler.charset, StandardCharset::German);
+
+        for byte in b"\x1b+%6" {
+            parser.advance(&mut handler, *byte, &mut Void);
+        }
+
+        assert_eq!(handler.index, CharsetIndex::G3);
+        assert_eq!(handler.charset, StandardCharset::Portuguese);
+    }
+
+    #[test]
+    fn parse_locking_and_single_shifts() {
+        let mut parser = Processor::new();
+        let mut handler = CharsetHandler::default();
+
+        for byte in b"\x1bo" {
+            parser.advance(&mut handler, *byte, &mut Void);
+        }
+
+        assert_eq!(handler.index, CharsetIndex::G3);
+        assert_eq!(handler.single_shift, None);
+
+        for byte in b"\x1bN" {
+            parser.advance(&mut handler, *byte, &mut Void);
+        }
+
+        assert_eq!(handler.index, CharsetIndex::G3);
+        assert_eq!(handler.single_shift, Some(CharsetIndex::G2));
+    }
+
+    struct C1Handler {
+        c1_controls: bool,
+        attr: Option<Attr>,
+        text: String,
+        encoding: C1Encoding,
+        color_reports: Vec<usize>,
+    }
+
+    impl C1Handler {
+        fn new(c1_controls: bool) -> C1Handler {
+            C1Handler {
+                c1_controls,
+                attr: None,
+                text: String::new(),
+                encoding: C1Encoding::SevenBit,
+                color_reports: Vec::new(),
+            }
+        }
+    }
+
+    impl Handler for C1Handler {
+        fn input(&mut self, c: char) {
+            self.text.push(c);
+        }
+
+        fn terminal_attribute(&mut self, attr: Attr) {
+            self.attr = Some(attr);
+        }
+
+        fn set_c1_encoding(&mut self, encoding: C1Encoding) {
+            self.encoding = encoding;
+        }
+
+        fn color_report<W: io::Write>(&mut self, _: &mut W, index: usize) {
+            self.color_reports.push(index);
+        }
+    }
+
+    impl TermInfo for C1Handler {
+        fn lines(&self) -> Line { Line(24) }
+        fn cols(&self) -> Column { Column(80) }
+        fn c1_controls(&self) -> bool { self.c1_controls }
+    }
+
+    #[test]
+    fn parse_8bit_csi() {
+        static BYTES: &'static [u8] = &[0x9b, b'1', b'm', 0xc4, 0x80];
+        let mut parser = Processor::new();
+        let mut handler = C1Handler::new(true);
+
+        for byte in &BYTES[..] {
+            parser.advance(&mut handler, *byte, &mut Void);
+        }
+
+        assert_eq!(handler.attr, Some(Attr::Bold));
+        assert_eq!(handler.text, "\u{100}");
+    }
+
+    #[test]
+    fn ignore_8bit_controls() {
+        static BYTES: &'static [u8] = &[0x9b, b'1', b'm'];
+        let mut parser = Processor::new();
+        let mut handler = C1Handler::new(false);
+
+        for byte in &BYTES[..] {
+            parser.advance(&mut handler, *byte, &mut Void);
+        }
+
+        assert_eq!(handler.attr, None);
+        assert_eq!(handler.text, "1m");
+    }
+
+    #[test]
+    fn parse_c1_transmission() {
+        let mut parser = Processor::new();
+        let mut handler = C1Handler::new(true);
+
+        for byte in b"\x1b G" {
+            parser.advance(&mut handler, *byte, &mut Void);
+        }
+        assert_eq!(handler.encoding, C1Encoding::EightBit);
+
+        for byte in b"\x1b F" {
+            parser.advance(&mut handler, *byte, &mut Void);
+        }
+        assert_eq!(handler.encoding, C1Encoding::SevenBit);
+    }
+
+    #[test]
+    fn parse_color_queries() {
+        let mut parser = Processor::new();
+        let mut handler = C1Handler::new(true);
+
+        for byte in b"\x1b]4;3;?\x07\x1b]11;?\x9c" {
+            parser.advance(&mut handler, *byte, &mut Void);
+        }
+
+        assert_eq!(handler.color_reports, vec![3, NamedColor::Background as usize]);
+    }
+
+    #[derive(Default)]
+    struct PrinterHandler {
+        printing: bool,
+        printed: Vec<u8>,
+        text: String,
+        screens: usize,
+    }
+
+    impl Handler for PrinterHandler {
+        fn input(&mut self, c: char) {
+            self.text.push(c);
+        }
+
+        fn print_screen(&mut self) {
+            self.screens += 1;
+        }
+
+        fn start_printer_controller(&mut self) {
+            self.printing = true;
+        }
+
+        fn printer_output(&mut self, bytes: &[u8]) {
+            self.printed.extend_from_slice(bytes);
+        }
+
+        fn stop_printer_controller(&mut self) {
+            self.printing = false;
+        }
+    }
+
+    impl TermInfo for PrinterHandler {
+        fn lines(&self) -> Line { Line(24) }
+        fn cols(&self) -> Column { Column(80) }
+    }
+
+    #[test]
+    fn parse_printer_controller() {
+        let mut parser = Processor::new();
+        let mut handler = PrinterHandler::default();
+
+        for byte in b"a\x1b[5ib\x1b\x1b[3m\x1b[4" {
+            parser.advance(&mut handler, *byte, &mut Void);
+        }
+        assert!(handler.printing);
+
+        for byte in b"i\x1b[ic" {
+            parser.advance(&mut handler, *byte, &mut Void);
+        }
+
+        assert!(!handler.printing);
+        assert_eq!(handler.printed, b"b\x1b\x1b[3m");
+        assert_eq!(handler.text, "ac");
+        assert_eq!(handler.screens, 1);
+    }
+
     #[test]
     fn parse_valid_rgb_color() {
         assert_eq!(parse_rgb_color(b"rgb:11/aa/ff"), Some(Rgb { r: 0x11, g: 0xaa, b: 0xff }));
@@ -1550,6 +2322,19 @@ mod tests {
         assert_eq!(parse_rgb_color(b"#11aaff"), Some(Rgb { r: 0x11, g: 0xaa, b: 0xff }));
     }
 
+    #[test]
+    fn parse_valid_file_url() {
+        assert_eq!(parse_file_url(b"file://host/home/user"), Some(String::from("/home/user")));
+        assert_eq!(parse_file_url(b"file:///tmp/a%20b"), Some(String::from("/tmp/a b")));
+    }
+
+    #[test]
+    fn parse_invalid_file_url() {
+        assert_eq!(parse_file_url(b"http://host/home"), None);
+        assert_eq!(parse_file_url(b"file://host"), None);
+        assert_eq!(parse_file_url(b"file:///tmp/%2"), None);
+    }
+
     #[test]
     fn parse_invalid_number() {
         assert_eq!(parse_number(b"1abc"), None);
@@ -12,9 +12,10 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 extern crate log;
-use clap::{Arg, App};
+use clap::{Arg, App, SubCommand};
 use index::{Line, Column};
 use config::{Dimensions, Shell};
+use encoding::Encoding;
 use std::path::{Path, PathBuf};
 use std::borrow::Cow;
 
@@ -24,6 +25,7 @@ const DEFAULT_TITLE: &str = "Alacritty";
 pub struct Options {
     pub live_config_reload: Option<bool>,
     pub print_events: bool,
+    pub print_keys: bool,
     pub ref_test: bool,
     pub dimensions: Option<Dimensions>,
     pub title: String,
@@ -31,6 +33,9 @@ pub struct Options {
     pub command: Option<Shell<'static>>,
     pub working_dir: Option<PathBuf>,
     pub config: Option<PathBuf>,
+    pub encoding: Option<Encoding>,
+    /// Register to replay once the shell started, and how often to replay it
+    pub replay: Option<(String, usize)>,
 }
 
 impl Default for Options {
@@ -38,6 +43,7 @@ impl Default for Options {
         Options {
             live_config_reload: None,
             print_events: false,
+            print_keys: false,
             ref_test: false,
             dimensions: None,
             title: DEFAULT_TITLE.to_owned(),
@@ -45,6 +51,8 @@ impl Default for Options {
             command: None,
             working_dir: None,
             config: None,
+            encoding: None,
+            replay: None,
         }
     }
 }
@@ -70,6 +78,9 @@ impl Options {
                  .conflicts_with("live-config-reload"))
             .arg(Arg::with_name("print-events")
                 .long("print-events"))
+            .arg(Arg::with_name("print-keys")
+                .long("print-keys")
+                .help("Print the name and scancode of each pressed key"))
             .arg(Arg::with_name("dimensions")
                 .long("dimensions")
                 .short("d")
@@ -100,6 +111,14 @@ impl Options {
                  .takes_value(true)
                  .help("Specify alternative configuration file \
                        [default: $XDG_CONFIG_HOME/alacritty/alacritty.yml]"))
+            .arg(Arg::with_name("encoding")
+                 .long("encoding")
+                 .takes_value(true)
+                 .validator(|label| match Encoding::from_label(&label) {
+                     Some(_) => Ok(()),
+                     None => Err(format!("unknown encoding `{}`", label)),
+                 })
+                 .help("Character encoding used by the application [default: UTF-8]"))
             .arg(Arg::with_name("command")
                 .long("command")
                 .short("e")
@@ -107,7 +126,23 @@ impl Options {
                 .takes_value(true)
                 .min_values(1)
                 .allow_hyphen_values(true)
+                .global(true)
                 .help("Command and args to execute (must be last argument)"))
+            .subcommand(SubCommand::with_name("replay")
+                .about("Replays a recorded keyboard macro once the shell started")
+                .arg(Arg::with_name("register")
+                    .required(true)
+                    .help("Register the macro was recorded into"))
+                .arg(Arg::with_name("count")
+                    .long("count")
+                    .short("n")
+                    .takes_value(true)
+                    .default_value("1")
+                    .validator(|count| match count.parse::<usize>() {
+                        Ok(_) => Ok(()),
+                        Err(_) => Err(format!("invalid count `{}`", count)),
+                    })
+                    .help("Number of times to replay the macro")))
             .get_matches();
 
         if matches.is_present("ref-test") {
@@ -118,6 +153,10 @@ impl Options {
             options.print_events = true;
         }
 
+        if matches.is_present("print-keys") {
+            options.print_keys = true;
+        }
+
         if matches.is_present("live-config-reload") {
             options.live_config_reload = Some(true);
         } else if matches.is_present("no-live-config-reload") {
@@ -157,6 +196,17 @@ impl Options {
             options.config = Some(PathBuf::from(path.to_string()));
         }
 
+        if let Some(label) = matches.value_of("encoding") {
+            options.encoding = Encoding::from_label(label);
+        }
+
+        if let Some(replay) = matches.subcommand_matches("replay") {
+            // Both arguments are validated by clap
+            let register = replay.value_of("register").unwrap().to_owned();
+            let count = replay.value_of("count").unwrap().parse().unwrap();
+            options.replay = Some((register, count));
+        }
+
         if let Some(mut args) = matches.values_of("command") {
             // The following unwrap is guaranteed to succeed.
             // If 'command' exists it must also have a first item since
@@ -23,8 +23,10 @@ use notify::{Watcher, watcher, DebouncedEvent, RecursiveMode};
 
 use glutin::ModifiersState;
 
-use input::{Action, Binding, MouseBinding, KeyBinding};
+use input::{Action, Binding, Condition, MouseBinding, MouseTrigger, KeyBinding, KeyTrigger};
+use snippet::Snippet;
 use index::{Line, Column};
+use encoding::Encoding;
 use ansi::CursorStyle;
 
 use util::fmt::Yellow;
@@ -191,6 +193,225 @@ impl Default for VisualBellConfig {
     }
 }
 
+#[derive(Debug, Deserialize)]
+pub struct UnicodeConfig {
+    /// Number of cells occupied by East Asian ambiguous width characters
+    #[serde(deserialize_with = "deserialize_ambiguous_width")]
+    #[serde(default="default_ambiguous_width")]
+    ambiguous_width: usize,
+
+    /// Let VS15 and VS16 switch characters between text and emoji presentation
+    #[serde(default, deserialize_with = "failure_default")]
+    presentation_selectors: bool,
+
+    /// Reorder right-to-left text for display
+    #[serde(default, deserialize_with = "failure_default")]
+    bidi: bool,
+}
+
+fn default_ambiguous_width() -> usize {
+    1
+}
+
+fn deserialize_ambiguous_width<'a, D>(deserializer: D) -> ::std::result::Result<usize, D::Error>
+    where D: de::Deserializer<'a>
+{
+    match usize::deserialize(deserializer) {
+        Ok(width @ 1) | Ok(width @ 2) => Ok(width),
+        Ok(width) => {
+            eprintln!("problem with config: ambiguous_width must be 1 or 2, got {}; Using `1`", width);
+            Ok(default_ambiguous_width())
+        },
+        Err(err) => {
+            eprintln!("problem with config: {}; Using `1`", err);
+            Ok(default_ambiguous_width())
+        },
+    }
+}
+
+impl UnicodeConfig {
+    /// Whether East Asian ambiguous width characters occupy two cells
+    #[inline]
+    pub fn ambiguous_wide(&self) -> bool {
+        self.ambiguous_width == 2
+    }
+
+    /// Whether variation selectors change the width of the preceding character
+    #[inline]
+    pub fn presentation_selectors(&self) -> bool {
+        self.presentation_selectors
+    }
+
+    /// Whether lines are reordered using the Unicode bidirectional algorithm
+    #[inline]
+    pub fn bidi(&self) -> bool {
+        self.bidi
+    }
+}
+
+impl Default for UnicodeConfig {
+    fn default() -> UnicodeConfig {
+        UnicodeConfig {
+            ambiguous_width: default_ambiguous_width(),
+            presentation_selectors: false,
+            bidi: false,
+        }
+    }
+}
+
+/// Characters typed while Alt is held
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+pub enum AltSend {
+    /// Prefix them with ESC
+    Escape,
+    /// Set their eighth bit (8-bit meta)
+    EightBit,
+    /// Send them unchanged
+    None,
+}
+
+impl Default for AltSend {
+    fn default() -> AltSend {
+        AltSend::Escape
+    }
+}
+
+/// Behavior of the Alt keys
+#[derive(Clone, Debug, Deserialize)]
+pub struct AltConfig {
+    #[serde(default, deserialize_with = "failure_default")]
+    send: AltSend,
+
+    /// Left Alt acts as meta
+    #[serde(default="true_bool", deserialize_with = "default_true_bool")]
+    left: bool,
+
+    /// Right Alt acts as meta
+    #[serde(default="true_bool", deserialize_with = "default_true_bool")]
+    right: bool,
+}
+
+impl AltConfig {
+    /// How characters typed while Alt is held are sent
+    #[inline]
+    pub fn send(&self) -> AltSend {
+        self.send
+    }
+
+    /// Whether the left Alt key acts as meta
+    #[inline]
+    pub fn left(&self) -> bool {
+        self.left
+    }
+
+    /// Whether the right Alt key acts as meta
+    #[inline]
+    pub fn right(&self) -> bool {
+        self.right
+    }
+}
+
+impl Default for AltConfig {
+    fn default() -> AltConfig {
+        AltConfig {
+            send: AltSend::default(),
+            left: true,
+            right: true,
+        }
+    }
+}
+
+/// Safety checks applied to pasted text
+#[derive(Clone, Debug, Deserialize)]
+pub struct PasteConfig {
+    /// Remove control characters other than tabs and line breaks
+    #[serde(default, deserialize_with = "failure_default")]
+    sanitize: bool,
+
+    /// Ask before pasting line breaks without bracketed paste
+    #[serde(default, deserialize_with = "failure_default")]
+    confirm_multiline: bool,
+
+    /// Ask before pasting more than this many bytes; 0 never asks
+    #[serde(deserialize_with = "deserialize_confirm_size")]
+    #[serde(default="default_confirm_size")]
+    confirm_size: usize,
+
+    /// Quoting of the paths of files dropped on the window
+    #[serde(default, deserialize_with = "failure_default")]
+    file_quoting: FileQuoting,
+}
+
+/// Quoting of file paths for the shell
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+pub enum FileQuoting {
+    /// Wrap them in single quotes
+    Single,
+    /// Wrap them in double quotes, escaping `"`, `$`, `` ` `` and `\`
+    Double,
+    /// Escape special characters with a backslash
+    Backslash,
+}
+
+impl Default for FileQuoting {
+    fn default() -> FileQuoting {
+        FileQuoting::Single
+    }
+}
+
+fn default_confirm_size() -> usize {
+    64 * 1024
+}
+
+fn deserialize_confirm_size<'a, D>(deserializer: D) -> ::std::result::Result<usize, D::Error>
+    where D: de::Deserializer<'a>
+{
+    match usize::deserialize(deserializer) {
+        Ok(size) => Ok(size),
+        Err(err) => {
+            eprintln!("problem with config: {}; Using default value", err);
+            Ok(default_confirm_size())
+        },
+    }
+}
+
+impl PasteConfig {
+    /// Whether control characters are removed from pasted text
+    #[inline]
+    pub fn sanitize(&self) -> bool {
+        self.sanitize
+    }
+
+    /// Whether pasting multiple lines without bracketed paste needs confirmation
+    #[inline]
+    pub fn confirm_multiline(&self) -> bool {
+        self.confirm_multiline
+    }
+
+    /// Size in bytes above which a paste needs confirmation; 0 never asks
+    #[inline]
+    pub fn confirm_size(&self) -> usize {
+        self.confirm_size
+    }
+
+    /// How the paths of dropped files are quoted
+    #[inline]
+    pub fn file_quoting(&self) -> FileQuoting {
+        self.file_quoting
+    }
+}
+
+impl Default for PasteConfig {
+    fn default() -> PasteConfig {
+        PasteConfig {
+            sanitize: false,
+            confirm_multiline: false,
+            confirm_size: default_confirm_size(),
+            file_quoting: FileQuoting::default(),
+        }
+    }
+}
+
 #[derive(Debug, Deserialize)]
 pub struct Shell<'a> {
     program: Cow<'a, str>,
@@ -227,6 +448,30 @@ impl<'a> Shell<'a> {
     }
 }
 
+/// Destination of media copy output
+#[derive(Debug, Default, Deserialize)]
+pub struct PrinterConfig {
+    /// File print jobs are appended to
+    #[serde(default, deserialize_with = "failure_default")]
+    file: Option<PathBuf>,
+
+    /// Command receiving every print job on stdin
+    #[serde(default, deserialize_with = "failure_default")]
+    command: Option<Shell<'static>>,
+}
+
+impl PrinterConfig {
+    #[inline]
+    pub fn file(&self) -> Option<&Path> {
+        self.file.as_ref().map(|path| path.as_path())
+    }
+
+    #[inline]
+    pub fn command(&self) -> Option<&Shell> {
+        self.command.as_ref()
+    }
+}
+
 /// Wrapper around f32 that represents an alpha value between 0.0 and 1.0
 #[derive(Clone, Copy, Debug)]
 pub struct Alpha(f32);
@@ -359,16 +604,32 @@ pub struct Config {
     #[serde(default, deserialize_with = "failure_default_vec")]
     mouse_bindings: Vec<MouseBinding>,
 
+    /// Time to wait for the next key of a chord binding
+    #[serde(default="default_chord_timeout", deserialize_with = "deserialize_chord_timeout")]
+    chord_timeout: Duration,
+
     #[serde(default, deserialize_with = "failure_default")]
     selection: Selection,
 
     #[serde(default, deserialize_with = "failure_default")]
     mouse: Mouse,
 
+    /// Behavior of the Alt keys
+    #[serde(default, deserialize_with = "failure_default")]
+    alt: AltConfig,
+
+    /// Safety checks for pasted text
+    #[serde(default, deserialize_with = "failure_default")]
+    paste: PasteConfig,
+
     /// Path to a shell program to run on startup
     #[serde(default, deserialize_with = "failure_default")]
     shell: Option<Shell<'static>>,
 
+    /// Destination of printer output
+    #[serde(default, deserialize_with = "failure_default")]
+    printer: PrinterConfig,
+
     /// Path where config was loaded from
     #[serde(default, deserialize_with = "failure_default")]
     config_path: Option<PathBuf>,
@@ -381,6 +642,10 @@ pub struct Config {
     #[serde(default="true_bool", deserialize_with = "default_true_bool")]
     dynamic_title: bool,
 
+    /// Interpret raw 8-bit C1 controls
+    #[serde(default="true_bool", deserialize_with = "default_true_bool")]
+    c1_controls: bool,
+
     /// Hide cursor when typing
     #[serde(default, deserialize_with = "failure_default")]
     hide_cursor_when_typing: bool,
@@ -396,6 +661,14 @@ pub struct Config {
     /// Number of spaces in one tab
     #[serde(default="default_tabspaces", deserialize_with = "deserialize_tabspaces")]
     tabspaces: usize,
+
+    /// Character width configuration
+    #[serde(default, deserialize_with = "failure_default")]
+    unicode: UnicodeConfig,
+
+    /// Character encoding of the pty
+    #[serde(default, deserialize_with = "deserialize_encoding")]
+    encoding: Encoding,
 }
 
 fn failure_default_vec<'a, D, T>(deserializer: D) -> ::std::result::Result<Vec<T>, D::Error>
@@ -429,6 +702,40 @@ fn default_tabspaces() -> usize {
     8
 }
 
+fn default_chord_timeout() -> Duration {
+    Duration::from_millis(1000)
+}
+
+fn deserialize_chord_timeout<'a, D>(deserializer: D) -> ::std::result::Result<Duration, D::Error>
+    where D: de::Deserializer<'a>
+{
+    match u64::deserialize(deserializer) {
+        Ok(timeout_ms) => Ok(Duration::from_millis(timeout_ms)),
+        Err(err) => {
+            eprintln!("problem with config: {}; Using default value", err);
+            Ok(default_chord_timeout())
+        },
+    }
+}
+
+fn deserialize_encoding<'a, D>(deserializer: D) -> ::std::result::Result<Encoding, D::Error>
+    where D: de::Deserializer<'a>
+{
+    match String::deserialize(deserializer) {
+        Ok(label) => match Encoding::from_label(&label) {
+            Some(encoding) => Ok(encoding),
+            None => {
+                eprintln!("problem with config: unknown encoding `{}`; Using `UTF-8`", label);
+                Ok(Encoding::default())
+            },
+        },
+        Err(err) => {
+            eprintln!("problem with config: {}; Using `UTF-8`", err);
+            Ok(Encoding::default())
+        },
+    }
+}
+
 fn deserialize_tabspaces<'a, D>(deserializer: D) -> ::std::result::Result<usize, D::Error>
     where D: de::Deserializer<'a>
 {
@@ -577,6 +884,32 @@ enum CommandWrapper {
     },
 }
 
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum ReplayWrapper {
+    Just(String),
+    WithCount {
+        register: String,
+        #[serde(default = "default_replay_count")]
+        count: usize,
+    },
+}
+
+fn default_replay_count() -> usize {
+    1
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum SnippetWrapper {
+    Just(String),
+    WithOptions {
+        text: String,
+        #[serde(default)]
+        paste: bool,
+    },
+}
+
 use ::term::{mode, TermMode};
 
 struct ModeWrapper {
@@ -622,72 +955,149 @@ impl<'a> de::Deserialize<'a> for ModeWrapper {
     }
 }
 
-struct MouseButton(::glutin::MouseButton);
+/// Newtype for implementing deserialize on mouse binding triggers
+struct MouseTriggerWrapper(MouseTrigger);
 
-impl MouseButton {
-    fn into_inner(self) -> ::glutin::MouseButton {
+impl MouseTriggerWrapper {
+    fn into_inner(self) -> MouseTrigger {
         self.0
     }
 }
 
-impl<'a> de::Deserialize<'a> for MouseButton {
+impl<'a> de::Deserialize<'a> for MouseTriggerWrapper {
     fn deserialize<D>(deserializer: D) -> ::std::result::Result<Self, D::Error>
         where D: de::Deserializer<'a>
     {
-        struct MouseButtonVisitor;
+        struct MouseTriggerVisitor;
 
-        impl<'a> Visitor<'a> for MouseButtonVisitor {
-            type Value = MouseButton;
+        impl<'a> Visitor<'a> for MouseTriggerVisitor {
+            type Value = MouseTriggerWrapper;
 
             fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
-                f.write_str("Left, Right, Middle, or a number")
+                f.write_str("Left, Right, Middle, WheelUp, WheelDown, WheelLeft, WheelRight, or a number")
             }
 
-            fn visit_str<E>(self, value: &str) -> ::std::result::Result<MouseButton, E>
+            fn visit_str<E>(self, value: &str) -> ::std::result::Result<MouseTriggerWrapper, E>
                 where E: de::Error,
             {
-                match value {
-                    "Left" => Ok(MouseButton(::glutin::MouseButton::Left)),
-                    "Right" => Ok(MouseButton(::glutin::MouseButton::Right)),
-                    "Middle" => Ok(MouseButton(::glutin::MouseButton::Middle)),
+                let trigger = match value {
+                    "Left" => MouseTrigger::Button(::glutin::MouseButton::Left),
+                    "Right" => MouseTrigger::Button(::glutin::MouseButton::Right),
+                    "Middle" => MouseTrigger::Button(::glutin::MouseButton::Middle),
+                    "WheelUp" => MouseTrigger::WheelUp,
+                    "WheelDown" => MouseTrigger::WheelDown,
+                    "WheelLeft" => MouseTrigger::WheelLeft,
+                    "WheelRight" => MouseTrigger::WheelRight,
                     _ => {
                         if let Ok(index) = u8::from_str(value) {
-                            Ok(MouseButton(::glutin::MouseButton::Other(index)))
+                            MouseTrigger::Button(::glutin::MouseButton::Other(index))
                         } else {
-                            Err(E::invalid_value(Unexpected::Str(value), &self))
+                            return Err(E::invalid_value(Unexpected::Str(value), &self));
                         }
                     }
+                };
+
+                Ok(MouseTriggerWrapper(trigger))
+            }
+
+            fn visit_u64<E>(self, value: u64) -> ::std::result::Result<MouseTriggerWrapper, E>
+                where E: de::Error,
+            {
+                if value <= u64::from(u8::max_value()) {
+                    let button = ::glutin::MouseButton::Other(value as u8);
+                    Ok(MouseTriggerWrapper(MouseTrigger::Button(button)))
+                } else {
+                    Err(E::invalid_value(Unexpected::Unsigned(value), &self))
                 }
             }
         }
 
-        deserializer.deserialize_str(MouseButtonVisitor)
+        deserializer.deserialize_str(MouseTriggerVisitor)
+    }
+}
+
+/// Newtype for implementing deserialize on a key name or scancode
+struct KeyTriggerWrapper(KeyTrigger);
+
+impl KeyTriggerWrapper {
+    fn into_inner(self) -> KeyTrigger {
+        self.0
     }
 }
 
+impl<'a> de::Deserialize<'a> for KeyTriggerWrapper {
+    fn deserialize<D>(deserializer: D) -> ::std::result::Result<Self, D::Error>
+        where D: de::Deserializer<'a>
+    {
+        struct KeyTriggerVisitor;
+
+        impl<'a> Visitor<'a> for KeyTriggerVisitor {
+            type Value = KeyTriggerWrapper;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a key name like A or F1, or a scancode")
+            }
+
+            fn visit_str<E>(self, value: &str) -> ::std::result::Result<KeyTriggerWrapper, E>
+                where E: de::Error,
+            {
+                if let Ok(scancode) = u32::from_str(value) {
+                    return Ok(KeyTriggerWrapper(KeyTrigger::Scancode(scancode)));
+                }
+
+                let key = Key::deserialize(de::IntoDeserializer::<E>::into_deserializer(value))?;
+                Ok(KeyTriggerWrapper(KeyTrigger::Keycode(key.to_glutin_key())))
+            }
+
+            fn visit_u64<E>(self, value: u64) -> ::std::result::Result<KeyTriggerWrapper, E>
+                where E: de::Error,
+            {
+                if value <= u64::from(u32::max_value()) {
+                    Ok(KeyTriggerWrapper(KeyTrigger::Scancode(value as u32)))
+                } else {
+                    Err(E::invalid_value(Unexpected::Unsigned(value), &self))
+                }
+            }
+        }
+
+        deserializer.deserialize_str(KeyTriggerVisitor)
+    }
+}
+
+/// Key of a chord binding pressed before its trigger
+#[derive(Deserialize)]
+struct KeyStroke {
+    key: KeyTriggerWrapper,
+    mods: Option<ModsWrapper>,
+}
+
 /// Bindings are deserialized into a `RawBinding` before being parsed as a
 /// `KeyBinding` or `MouseBinding`.
 struct RawBinding {
-    key: Option<::glutin::VirtualKeyCode>,
-    mouse: Option<::glutin::MouseButton>,
+    key: Option<KeyTrigger>,
+    leader: Vec<(KeyTrigger, ModifiersState)>,
+    mouse: Option<MouseTrigger>,
     mods: ModifiersState,
     mode: TermMode,
     notmode: TermMode,
     action: Action,
+    condition: Option<Condition>,
 }
 
 impl RawBinding {
     fn into_mouse_binding(self) -> ::std::result::Result<MouseBinding, Self> {
-        if let Some(mouse) = self.mouse {
-            Ok(Binding {
+        match self.mouse {
+            // Chords are only supported for keys
+            Some(mouse) if self.leader.is_empty() => Ok(Binding {
                 trigger: mouse,
+                leader: Vec::new(),
                 mods: self.mods,
                 action: self.action,
                 mode: self.mode,
                 notmode: self.notmode,
-            })
-        } else {
-            Err(self)
+                condition: self.condition,
+            }),
+            _ => Err(self),
         }
     }
 
@@ -695,10 +1105,12 @@ impl RawBinding {
         if let Some(key) = self.key {
             Ok(KeyBinding {
                 trigger: key,
+                leader: self.leader,
                 mods: self.mods,
                 action: self.action,
                 mode: self.mode,
                 notmode: self.notmode,
+                condition: self.condition,
             })
         } else {
             Err(self)
@@ -712,12 +1124,19 @@ impl<'a> de::Deserialize<'a> for RawBinding {
     {
         enum Field {
             Key,
+            Leader,
             Mods,
             Mode,
             Action,
             Chars,
             Mouse,
+            DoubleClick,
             Command,
+            Process,
+            Title,
+            Record,
+            Replay,
+            Snippet,
         }
 
         impl<'a> de::Deserialize<'a> for Field {
@@ -727,7 +1146,9 @@ impl<'a> de::Deserialize<'a> for RawBinding {
                 struct FieldVisitor;
 
                 static FIELDS: &'static [&'static str] = &[
-                        "key", "mods", "mode", "action", "chars", "mouse", "command",
+                        "key", "leader", "mods", "mode", "action", "chars", "mouse",
+                        "double_click", "command", "process", "title", "record", "replay",
+                        "snippet",
                 ];
 
                 impl<'a> Visitor<'a> for FieldVisitor {
@@ -742,12 +1163,19 @@ impl<'a> de::Deserialize<'a> for RawBinding {
                     {
                         match value {
                             "key" => Ok(Field::Key),
+                            "leader" => Ok(Field::Leader),
                             "mods" => Ok(Field::Mods),
                             "mode" => Ok(Field::Mode),
                             "action" => Ok(Field::Action),
                             "chars" => Ok(Field::Chars),
                             "mouse" => Ok(Field::Mouse),
+                            "double_click" => Ok(Field::DoubleClick),
                             "command" => Ok(Field::Command),
+                            "process" => Ok(Field::Process),
+                            "title" => Ok(Field::Title),
+                            "record" => Ok(Field::Record),
+                            "replay" => Ok(Field::Replay),
+                            "snippet" => Ok(Field::Snippet),
                             _ => Err(E::unknown_field(value, FIELDS)),
                         }
                     }
@@ -772,13 +1200,20 @@ impl<'a> de::Deserialize<'a> for RawBinding {
                 where V: MapAccess<'a>,
             {
                 let mut mods: Option<ModifiersState> = None;
-                let mut key: Option<::glutin::VirtualKeyCode> = None;
+                let mut key: Option<KeyTrigger> = None;
+                let mut leader: Option<Vec<KeyStroke>> = None;
                 let mut chars: Option<String> = None;
                 let mut action: Option<::input::Action> = None;
                 let mut mode: Option<TermMode> = None;
                 let mut not_mode: Option<TermMode> = None;
-                let mut mouse: Option<::glutin::MouseButton> = None;
+                let mut mouse: Option<MouseTrigger> = None;
+                let mut double_click: Option<bool> = None;
                 let mut command: Option<CommandWrapper> = None;
+                let mut process: Option<String> = None;
+                let mut title: Option<String> = None;
+                let mut record: Option<String> = None;
+                let mut replay: Option<ReplayWrapper> = None;
+                let mut snippet: Option<Snippet> = None;
 
                 use ::serde::de::Error;
 
@@ -789,8 +1224,14 @@ impl<'a> de::Deserialize<'a> for RawBinding {
                                 return Err(<V::Error as Error>::duplicate_field("key"));
                             }
 
-                            let coherent_key = map.next_value::<Key>()?;
-                            key = Some(coherent_key.to_glutin_key());
+                            key = Some(map.next_value::<KeyTriggerWrapper>()?.into_inner());
+                        },
+                        Field::Leader => {
+                            if leader.is_some() {
+                                return Err(<V::Error as Error>::duplicate_field("leader"));
+                            }
+
+                            leader = Some(map.next_value()?);
                         },
                         Field::Mods => {
                             if mods.is_some() {
@@ -823,11 +1264,18 @@ impl<'a> de::Deserialize<'a> for RawBinding {
                             chars = Some(map.next_value()?);
                         },
                         Field::Mouse => {
-                            if chars.is_some() {
+                            if mouse.is_some() {
                                 return Err(<V::Error as Error>::duplicate_field("mouse"));
                             }
 
-                            mouse = Some(map.next_value::<MouseButton>()?.into_inner());
+                            mouse = Some(map.next_value::<MouseTriggerWrapper>()?.into_inner());
+                        },
+                        Field::DoubleClick => {
+                            if double_click.is_some() {
+                                return Err(<V::Error as Error>::duplicate_field("double_click"));
+                            }
+
+                            double_click = Some(map.next_value()?);
                         },
                         Field::Command => {
                             if command.is_some() {
@@ -836,13 +1284,64 @@ impl<'a> de::Deserialize<'a> for RawBinding {
 
                             command = Some(map.next_value::<CommandWrapper>()?);
                         },
+                        Field::Process => {
+                            if process.is_some() {
+                                return Err(<V::Error as Error>::duplicate_field("process"));
+                            }
+
+                            process = Some(map.next_value()?);
+                        },
+                        Field::Title => {
+                            if title.is_some() {
+                                return Err(<V::Error as Error>::duplicate_field("title"));
+                            }
+
+                            title = Some(map.next_value()?);
+                        },
+                        Field::Record => {
+                            if record.is_some() {
+                                return Err(<V::Error as Error>::duplicate_field("record"));
+                            }
+
+                            record = Some(map.next_value()?);
+                        },
+                        Field::Replay => {
+                            if replay.is_some() {
+                                return Err(<V::Error as Error>::duplicate_field("replay"));
+                            }
+
+                            replay = Some(map.next_value::<ReplayWrapper>()?);
+                        },
+                        Field::Snippet => {
+                            if snippet.is_some() {
+                                return Err(<V::Error as Error>::duplicate_field("snippet"));
+                            }
+
+                            let (text, paste) = match map.next_value::<SnippetWrapper>()? {
+                                SnippetWrapper::Just(text) => (text, false),
+                                SnippetWrapper::WithOptions { text, paste } => (text, paste),
+                            };
+                            snippet = Some(Snippet::new(&text, paste).map_err(V::Error::custom)?);
+                        },
                     }
                 }
 
-                let action = match (action, chars, command) {
-                    (Some(action), None, None) => action,
-                    (None, Some(chars), None) => Action::Esc(chars),
-                    (None, None, Some(cmd)) => {
+                let macro_action = match (record, replay) {
+                    (Some(register), None) => Some(Action::RecordMacro(register)),
+                    (None, Some(ReplayWrapper::Just(register))) => {
+                        Some(Action::ReplayMacro(register, 1))
+                    },
+                    (None, Some(ReplayWrapper::WithCount { register, count })) => {
+                        Some(Action::ReplayMacro(register, count))
+                    },
+                    (None, None) => None,
+                    _ => return Err(V::Error::custom("must specify only record or replay")),
+                };
+
+                let action = match (action, chars, command, snippet, macro_action) {
+                    (Some(action), None, None, None, None) => action,
+                    (None, Some(chars), None, None, None) => Action::Esc(chars),
+                    (None, None, Some(cmd), None, None) => {
                         match cmd {
                             CommandWrapper::Just(program) => {
                                 Action::Command(program, vec![])
@@ -852,8 +1351,18 @@ impl<'a> de::Deserialize<'a> for RawBinding {
                             },
                         }
                     },
-                    (None, None, None) => return Err(V::Error::custom("must specify chars, action or command")),
-                    _ => return Err(V::Error::custom("must specify only chars, action or command")),
+                    (None, None, None, Some(snippet), None) => Action::Snippet(snippet),
+                    (None, None, None, None, Some(action)) => action,
+                    (None, None, None, None, None) => {
+                        return Err(V::Error::custom(
+                            "must specify chars, action, command, snippet, record or replay"
+                        ));
+                    },
+                    _ => {
+                        return Err(V::Error::custom(
+                            "must specify only chars, action, command, snippet, record or replay"
+                        ));
+                    },
                 };
 
                 let mode = mode.unwrap_or_else(TermMode::empty);
@@ -864,19 +1373,61 @@ impl<'a> de::Deserialize<'a> for RawBinding {
                     return Err(V::Error::custom("bindings require mouse button or key"));
                 }
 
+                let mouse = match (mouse, double_click.unwrap_or(false)) {
+                    (Some(MouseTrigger::Button(button)), true) => {
+                        Some(MouseTrigger::DoubleClick(button))
+                    },
+                    (_, true) => {
+                        return Err(V::Error::custom("double_click requires a mouse button"));
+                    },
+                    (mouse, false) => mouse,
+                };
+
+                // A leading `~` negates the condition, like it does for modes
+                let negate = |value: String| {
+                    if value.starts_with('~') {
+                        (value[1..].to_owned(), true)
+                    } else {
+                        (value, false)
+                    }
+                };
+                let condition = match (process, title) {
+                    (Some(process), None) => {
+                        let (name, negated) = negate(process);
+                        Some(Condition::Process { name, negated })
+                    },
+                    (None, Some(title)) => {
+                        let (text, negated) = negate(title);
+                        Some(Condition::Title { text, negated })
+                    },
+                    (None, None) => None,
+                    _ => return Err(V::Error::custom("must specify only process or title")),
+                };
+
+                let leader = leader.unwrap_or_default()
+                    .into_iter()
+                    .map(|stroke| {
+                        let mods = stroke.mods.map(ModsWrapper::into_inner).unwrap_or_default();
+                        (stroke.key.into_inner(), mods)
+                    })
+                    .collect();
+
                 Ok(RawBinding {
                     mode,
                     notmode: not_mode,
                     action,
                     key,
+                    leader,
                     mouse,
                     mods,
+                    condition,
                 })
             }
         }
 
         const FIELDS: &[&str] = &[
-            "key", "mods", "mode", "action", "chars", "mouse", "command",
+            "key", "leader", "mods", "mode", "action", "chars", "mouse", "double_click", "command",
+            "process", "title", "record", "replay", "snippet",
         ];
 
         deserializer.deserialize_struct("RawBinding", FIELDS, RawBindingVisitor)
@@ -1269,6 +1820,12 @@ impl Config {
         &self.key_bindings[..]
     }
 
+    /// Time to wait for the next key of a chord binding
+    #[inline]
+    pub fn chord_timeout(&self) -> Duration {
+        self.chord_timeout
+    }
+
     pub fn mouse_bindings(&self) -> &[MouseBinding] {
         &self.mouse_bindings[..]
     }
@@ -1277,6 +1834,14 @@ impl Config {
         &self.mouse
     }
 
+    pub fn alt(&self) -> &AltConfig {
+        &self.alt
+    }
+
+    pub fn paste(&self) -> &PasteConfig {
+        &self.paste
+    }
+
     pub fn selection(&self) -> &Selection {
         &self.selection
     }
@@ -1285,6 +1850,16 @@ impl Config {
         self.tabspaces
     }
 
+    /// Character encoding used by the application
+    #[inline]
+    pub fn encoding(&self) -> Encoding {
+        self.encoding
+    }
+
+    pub fn unicode(&self) -> &UnicodeConfig {
+        &self.unicode
+    }
+
     pub fn padding(&self) -> &Delta<u8> {
         self.padding.as_ref()
             .unwrap_or(&self.window.padding)
@@ -1346,6 +1921,11 @@ impl Config {
         self.shell.as_ref()
     }
 
+    #[inline]
+    pub fn printer(&self) -> &PrinterConfig {
+        &self.printer
+    }
+
     pub fn env(&self) -> &HashMap<String, String> {
         &self.env
     }
@@ -1373,6 +1953,12 @@ impl Config {
         self.dynamic_title
     }
 
+    /// Whether raw 8-bit C1 controls are interpreted or ignored
+    #[inline]
+    pub fn c1_controls(&self) -> bool {
+        self.c1_controls
+    }
+
     pub fn load_from<P: Into<PathBuf>>(path: P) -> Result<Config> {
         let path = path.into();
         let raw = Config::read_file(path.as_path())?;
@@ -1703,7 +2289,10 @@ impl Monitor {
 
 #[cfg(test)]
 mod tests {
-    use super::Config;
+    use glutin::{MouseButton, VirtualKeyCode};
+
+    use input::{Action, Condition, KeyBinding, KeyTrigger, MouseBinding, MouseTrigger};
+    use super::{Config, FileQuoting, PasteConfig, default_confirm_size};
 
     #[cfg(target_os="macos")]
     static ALACRITTY_YML: &'static str =
@@ -1720,9 +2309,121 @@ mod tests {
         // Sanity check that mouse bindings are being parsed
         assert!(config.mouse_bindings.len() >= 1);
 
+        // Pasting the selection works with and without Shift
+        let middle = MouseTrigger::Button(MouseButton::Middle);
+        for &shift in &[false, true] {
+            assert!(config.mouse_bindings.iter().any(|binding| {
+                binding.trigger == middle && binding.mods.shift == shift &&
+                    !binding.mods.ctrl && !binding.mods.alt && !binding.mods.logo
+            }));
+        }
+
         // Sanity check that key bindings are being parsed
         assert!(config.key_bindings.len() >= 1);
     }
+
+    #[test]
+    fn parse_mouse_bindings() {
+        let parse = |yaml: &str| ::serde_yaml::from_str::<MouseBinding>(yaml).map(|binding| binding.trigger);
+
+        let wheel = ::serde_yaml::from_str::<MouseBinding>(
+            "{ mouse: WheelUp, mods: Control, action: IncreaseFontSize }"
+        ).unwrap();
+        assert_eq!(wheel.trigger, MouseTrigger::WheelUp);
+        assert!(wheel.mods.ctrl && !wheel.mods.shift && !wheel.mods.alt && !wheel.mods.logo);
+
+        assert_eq!(parse("{ mouse: WheelRight, action: Paste }").unwrap(), MouseTrigger::WheelRight);
+        assert_eq!(
+            parse("{ mouse: 8, action: Paste }").unwrap(),
+            MouseTrigger::Button(MouseButton::Other(8))
+        );
+        assert_eq!(
+            parse("{ mouse: Left, double_click: true, action: Copy }").unwrap(),
+            MouseTrigger::DoubleClick(MouseButton::Left)
+        );
+        assert!(parse("{ mouse: WheelDown, double_click: true, action: Copy }").is_err());
+    }
+
+    #[test]
+    fn parse_scancode_bindings() {
+        let parse = |yaml: &str| ::serde_yaml::from_str::<KeyBinding>(yaml);
+
+        assert_eq!(parse("{ key: 38, action: Copy }").unwrap().trigger, KeyTrigger::Scancode(38));
+        assert_eq!(
+            parse("{ key: F1, action: Copy }").unwrap().trigger,
+            KeyTrigger::Keycode(VirtualKeyCode::F1)
+        );
+        assert_eq!(
+            parse("{ key: C, leader: [{ key: 38, mods: Control }], action: Copy }").unwrap().leader[0].0,
+            KeyTrigger::Scancode(38)
+        );
+        assert!(parse("{ key: NotAKey, action: Copy }").is_err());
+    }
+
+    #[test]
+    fn parse_macro_bindings() {
+        let parse = |yaml: &str| ::serde_yaml::from_str::<KeyBinding>(yaml).map(|binding| binding.action);
+
+        match parse("{ key: F1, record: a }").unwrap() {
+            Action::RecordMacro(ref register) => assert_eq!(register, "a"),
+            action => panic!("unexpected action {:?}", action),
+        }
+        match parse("{ key: F2, replay: a }").unwrap() {
+            Action::ReplayMacro(ref register, 1) => assert_eq!(register, "a"),
+            action => panic!("unexpected action {:?}", action),
+        }
+        match parse("{ key: F3, replay: { register: a, count: 5 } }").unwrap() {
+            Action::ReplayMacro(ref register, 5) => assert_eq!(register, "a"),
+            action => panic!("unexpected action {:?}", action),
+        }
+        assert!(parse("{ key: F4, record: a, replay: a }").is_err());
+        assert!(parse("{ key: F4, record: a, chars: x }").is_err());
+    }
+
+    #[test]
+    fn parse_snippet_bindings() {
+        let parse = |yaml: &str| ::serde_yaml::from_str::<KeyBinding>(yaml).map(|binding| binding.action);
+
+        match parse("{ key: F1, snippet: \"cd {cwd}\" }").unwrap() {
+            Action::Snippet(ref snippet) => assert!(!snippet.paste()),
+            action => panic!("unexpected action {:?}", action),
+        }
+        match parse("{ key: F1, snippet: { text: \"{selection}\", paste: true } }").unwrap() {
+            Action::Snippet(ref snippet) => assert!(snippet.paste()),
+            action => panic!("unexpected action {:?}", action),
+        }
+        assert!(parse("{ key: F1, snippet: \"{home}\" }").is_err());
+        assert!(parse("{ key: F1, snippet: x, chars: x }").is_err());
+    }
+
+    #[test]
+    fn parse_binding_conditions() {
+        let parse = |yaml: &str| ::serde_yaml::from_str::<KeyBinding>(yaml).map(|binding| binding.condition);
+
+        assert_eq!(
+            parse("{ key: C, mods: Control|Shift, action: Copy, process: ~vim }").unwrap(),
+            Some(Condition::Process { name: String::from("vim"), negated: true })
+        );
+        assert_eq!(
+            parse("{ key: C, action: Copy, title: htop }").unwrap(),
+            Some(Condition::Title { text: String::from("htop"), negated: false })
+        );
+        assert_eq!(parse("{ key: C, action: Copy }").unwrap(), None);
+        assert!(parse("{ key: C, action: Copy, process: vim, title: vim }").is_err());
+    }
+
+    #[test]
+    fn parse_paste_config() {
+        let paste = ::serde_yaml::from_str::<PasteConfig>("{ sanitize: true, confirm_size: 0 }").unwrap();
+        assert!(paste.sanitize());
+        assert!(!paste.confirm_multiline());
+        assert_eq!(paste.confirm_size(), 0);
+        assert_eq!(paste.file_quoting(), FileQuoting::Single);
+
+        let paste = ::serde_yaml::from_str::<PasteConfig>("{ confirm_size: lots, file_quoting: Backslash }").unwrap();
+        assert_eq!(paste.confirm_size(), default_confirm_size());
+        assert_eq!(paste.file_quoting(), FileQuoting::Backslash);
+    }
 }
 
 #[cfg_attr(feature = "clippy", allow(enum_variant_names))]
@@ -22,6 +22,7 @@ use Rgb;
 use cli;
 use config::Config;
 use font::{self, Rasterize};
+use index::Line;
 use meter::Meter;
 use renderer::{self, GlyphCache, QuadRenderer};
 use selection::Selection;
@@ -97,6 +98,7 @@ pub struct Display {
     tx: mpsc::Sender<(u32, u32)>,
     meter: Meter,
     font_size: font::Size,
+    status_line_rows: usize,
     size_info: SizeInfo,
     last_background_color: Rgb,
 }
@@ -206,6 +208,7 @@ impl Display {
             rx,
             meter: Meter::new(),
             font_size: font::Size::new(0.),
+            status_line_rows: 0,
             size_info,
             last_background_color: background_color,
         })
@@ -299,15 +302,26 @@ impl Display {
             }
         }
 
+        // Status line shown or hidden; the I/O loop already resized the pty,
+        // but everything else has to learn about the main display's new size
+        if terminal.status_line_rows() != self.status_line_rows {
+            self.status_line_rows = terminal.status_line_rows();
+
+            if new_size == None {
+                new_size = Some((self.size_info.width as u32,
+                                 self.size_info.height as u32));
+            }
+        }
+
         // Receive any resize events; only call gl::Viewport on last
         // available
         if let Some((w, h)) = new_size.take() {
             self.size_info.width = w as f32;
             self.size_info.height = h as f32;
 
-            let size = &self.size_info;
-            terminal.resize(size);
+            terminal.resize(&self.size_info);
 
+            let size = &terminal.pty_size_info();
             for item in items {
                 item.on_resize(size)
             }
@@ -323,7 +337,14 @@ impl Display {
     /// A reference to Term whose state is being drawn must be provided.
     ///
     /// This call may block if vsync is enabled
-    pub fn draw(&mut self, mut terminal: MutexGuard<Term>, config: &Config, selection: Option<&Selection>) {
+    pub fn draw(
+        &mut self,
+        mut terminal: MutexGuard<Term>,
+        config: &Config,
+        selection: Option<&Selection>,
+        pending_chord: Option<String>,
+        paste_prompt: Option<String>,
+    ) {
         // Clear dirty flag
         terminal.dirty = !terminal.visual_bell.completed();
 
@@ -374,6 +395,11 @@ impl Display {
                         terminal.renderable_cells(config, selection, window_focused),
                         glyph_cache,
                     );
+
+                    // Draw the status line below the grid
+                    if let Some(cells) = terminal.renderable_status_line(config, window_focused) {
+                        api.render_cells(cells, glyph_cache);
+                    }
                 });
             }
 
@@ -382,7 +408,25 @@ impl Display {
                 let timing = format!("{:.3} usec", self.meter.average());
                 let color = Rgb { r: 0xd5, g: 0x4e, b: 0x53 };
                 self.renderer.with_api(config, &size_info, visual_bell_intensity, |mut api| {
-                    api.render_string(&timing[..], glyph_cache, color);
+                    api.render_string(&timing[..], Line(23), glyph_cache, color);
+                });
+            }
+
+            // Show the keys of a pending chord binding in the bottom line
+            if let Some(keys) = pending_chord {
+                let line = size_info.lines() - 1;
+                let color = config.colors().normal.yellow;
+                self.renderer.with_api(config, &size_info, visual_bell_intensity, |mut api| {
+                    api.render_string(&keys[..], line, glyph_cache, color);
+                });
+            }
+
+            // Ask whether a risky paste should be written to the pty
+            if let Some(prompt) = paste_prompt {
+                let line = size_info.lines() - 1;
+                let color = config.colors().normal.red;
+                self.renderer.with_api(config, &size_info, visual_bell_intensity, |mut api| {
+                    api.render_string(&prompt[..], line, glyph_cache, color);
                 });
             }
         }
@@ -0,0 +1,369 @@
+// Copyright 2016 Joe Wilm, The Alacritty Project Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+//! Character encoding of the pty stream
+//!
+//! The parser only understands UTF-8. When the application uses a legacy
+//! encoding, its output is transcoded to UTF-8 before parsing and everything
+//! written to the pty is transcoded back.
+use std::borrow::Cow;
+use std::fmt;
+use std::io;
+use std::str;
+
+use encoding_rs::{self, CoderResult, EncoderResult};
+
+/// Code page 437 characters for the bytes 0x80 to 0xff
+static CP437: [char; 128] = [
+    'Ç', 'ü', 'é', 'â', 'ä', 'à', 'å', 'ç', 'ê', 'ë', 'è', 'ï', 'î', 'ì', 'Ä', 'Å',
+    'É', 'æ', 'Æ', 'ô', 'ö', 'ò', 'û', 'ù', 'ÿ', 'Ö', 'Ü', '¢', '£', '¥', '₧', 'ƒ',
+    'á', 'í', 'ó', 'ú', 'ñ', 'Ñ', 'ª', 'º', '¿', '⌐', '¬', '½', '¼', '¡', '«', '»',
+    '░', '▒', '▓', '│', '┤', '╡', '╢', '╖', '╕', '╣', '║', '╗', '╝', '╜', '╛', '┐',
+    '└', '┴', '┬', '├', '─', '┼', '╞', '╟', '╚', '╔', '╩', '╦', '╠', '═', '╬', '╧',
+    '╨', '╤', '╥', '╙', '╘', '╒', '╓', '╫', '╪', '┘', '┌', '█', '▄', '▌', '▐', '▀',
+    'α', 'ß', 'Γ', 'π', 'Σ', 'σ', 'µ', 'τ', 'Φ', 'Θ', 'Ω', 'δ', '∞', 'φ', 'ε', '∩',
+    '≡', '±', '≥', '≤', '⌠', '⌡', '÷', '≈', '°', '∙', '·', '√', 'ⁿ', '²', '■', '\u{a0}',
+];
+
+/// Encoding used by the application running in the terminal
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    Utf8,
+
+    /// ISO 8859-1; the bytes 0x80 to 0x9f are C1 controls
+    Latin1,
+
+    /// IBM PC code page 437
+    Cp437,
+
+    /// Any other encoding known to `encoding_rs`, such as EUC-JP
+    Other(&'static encoding_rs::Encoding),
+}
+
+impl Default for Encoding {
+    fn default() -> Encoding {
+        Encoding::Utf8
+    }
+}
+
+impl fmt::Debug for Encoding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl Encoding {
+    /// Look up an encoding by one of its names, ignoring case
+    ///
+    /// Labels from the WHATWG encoding standard are accepted, except that
+    /// `latin1` and `iso-8859-1` refer to the real ISO 8859-1 rather than
+    /// windows-1252. Encodings which are not ASCII compatible, like UTF-16,
+    /// can't carry terminal control sequences and are rejected.
+    pub fn from_label(label: &str) -> Option<Encoding> {
+        let label = label.trim().to_lowercase();
+        match &label[..] {
+            "utf-8" | "utf8" => return Some(Encoding::Utf8),
+            "latin1" | "iso-8859-1" | "iso8859-1" | "iso_8859-1" | "l1" => {
+                return Some(Encoding::Latin1)
+            },
+            "cp437" | "ibm437" | "437" => return Some(Encoding::Cp437),
+            _ => (),
+        }
+
+        encoding_rs::Encoding::for_label_no_replacement(label.as_bytes())
+            .and_then(|encoding| {
+                if encoding == encoding_rs::UTF_8 {
+                    Some(Encoding::Utf8)
+                } else if encoding.is_ascii_compatible() {
+                    Some(Encoding::Other(encoding))
+                } else {
+                    None
+                }
+            })
+    }
+
+    /// Canonical name of the encoding
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Encoding::Utf8 => "UTF-8",
+            Encoding::Latin1 => "ISO-8859-1",
+            Encoding::Cp437 => "IBM437",
+            Encoding::Other(encoding) => encoding.name(),
+        }
+    }
+
+    /// Create a decoder for the output of the application
+    pub fn decoder(&self) -> Decoder {
+        let inner = match *self {
+            Encoding::Other(encoding) => Some(encoding.new_decoder_without_bom_handling()),
+            _ => None,
+        };
+
+        Decoder {
+            encoding: *self,
+            inner,
+            buf: Vec::new(),
+        }
+    }
+
+    /// Transcode UTF-8 input for the application
+    ///
+    /// Bytes which aren't valid UTF-8, like mouse reports with large
+    /// coordinates, are passed on unchanged. Characters which have no
+    /// representation in the encoding are replaced by `?`.
+    pub fn encode<'a>(&self, input: Cow<'a, [u8]>) -> Cow<'a, [u8]> {
+        if *self == Encoding::Utf8 || input.is_ascii() {
+            return input;
+        }
+
+        let mut output = Vec::with_capacity(input.len());
+        let mut input = &input[..];
+        while !input.is_empty() {
+            let (text, invalid) = match str::from_utf8(input) {
+                Ok(text) => (text, input.len()),
+                Err(err) => {
+                    let (valid, _) = input.split_at(err.valid_up_to());
+                    (str::from_utf8(valid).unwrap(), err.valid_up_to() + 1)
+                },
+            };
+
+            self.encode_str(text, &mut output);
+            output.extend_from_slice(&input[text.len()..invalid]);
+            input = &input[invalid..];
+        }
+
+        Cow::Owned(output)
+    }
+
+    fn encode_str(&self, mut text: &str, output: &mut Vec<u8>) {
+        // 8-bit C1 controls in replies are sent as single bytes, the same
+        // way the decoder expects them from the application
+        while let Some(start) = text.find(|c| c >= '\u{80}' && c <= '\u{9f}') {
+            self.encode_text(&text[..start], output);
+            output.push(text[start..].chars().next().unwrap() as u8);
+            text = &text[start + 2..];
+        }
+
+        self.encode_text(text, output);
+    }
+
+    fn encode_text(&self, mut text: &str, output: &mut Vec<u8>) {
+        if text.is_empty() {
+            return;
+        }
+
+        let mut encoder = match *self {
+            Encoding::Utf8 => return output.extend_from_slice(text.as_bytes()),
+            Encoding::Latin1 => {
+                let bytes = text.chars().map(|c| if c <= '\u{ff}' { c as u8 } else { b'?' });
+                return output.extend(bytes);
+            },
+            Encoding::Cp437 => {
+                let bytes = text.chars().map(|c| {
+                    if c.is_ascii() {
+                        c as u8
+                    } else {
+                        CP437.iter().position(|&cp| cp == c).map_or(b'?', |i| i as u8 + 0x80)
+                    }
+                });
+                return output.extend(bytes);
+            },
+            Encoding::Other(encoding) => encoding.new_encoder(),
+        };
+
+        loop {
+            let needed = encoder.max_buffer_length_from_utf8_without_replacement(text.len());
+            output.reserve(needed.unwrap_or(text.len()));
+
+            let (result, read) = encoder
+                .encode_from_utf8_to_vec_without_replacement(text, output, true);
+            text = &text[read..];
+
+            match result {
+                EncoderResult::InputEmpty => break,
+                EncoderResult::OutputFull => (),
+                EncoderResult::Unmappable(_) => output.push(b'?'),
+            }
+        }
+    }
+
+    /// Wrap a writer so everything written to it is transcoded
+    pub fn writer<W: io::Write>(&self, inner: W) -> Writer<W> {
+        Writer { encoding: *self, inner }
+    }
+}
+
+/// Incremental decoder for the output of the application
+///
+/// Multi-byte characters may be split across reads, so partial sequences are
+/// kept until the rest arrives.
+pub struct Decoder {
+    encoding: Encoding,
+    inner: Option<encoding_rs::Decoder>,
+    buf: Vec<u8>,
+}
+
+impl Decoder {
+    /// Transcode the next chunk of output to UTF-8
+    pub fn decode<'a>(&'a mut self, input: &'a [u8]) -> &'a [u8] {
+        self.buf.clear();
+        match self.encoding {
+            Encoding::Utf8 => return input,
+            Encoding::Latin1 => {
+                for &byte in input {
+                    // C1 controls are left as raw bytes for the parser
+                    match byte {
+                        0x00...0x9f => self.buf.push(byte),
+                        _ => push_char(&mut self.buf, char::from(byte)),
+                    }
+                }
+            },
+            Encoding::Cp437 => {
+                for &byte in input {
+                    match byte {
+                        0x00...0x7f => self.buf.push(byte),
+                        _ => push_char(&mut self.buf, CP437[byte as usize - 0x80]),
+                    }
+                }
+            },
+            Encoding::Other(_) => {
+                let decoder = self.inner.as_mut().unwrap();
+                let mut input = input;
+                loop {
+                    let start = self.buf.len();
+                    let needed = decoder.max_utf8_buffer_length(input.len());
+                    self.buf.resize(start + needed.unwrap_or(input.len() * 3 + 4), 0);
+
+                    let (result, read, written, _) =
+                        decoder.decode_to_utf8(input, &mut self.buf[start..], false);
+                    self.buf.truncate(start + written);
+                    input = &input[read..];
+
+                    if let CoderResult::InputEmpty = result {
+                        break;
+                    }
+                }
+            },
+        }
+
+        &self.buf
+    }
+}
+
+#[inline]
+fn push_char(buf: &mut Vec<u8>, c: char) {
+    let mut bytes = [0; 4];
+    buf.extend_from_slice(c.encode_utf8(&mut bytes).as_bytes());
+}
+
+/// Writer transcoding UTF-8 to the encoding of the application
+pub struct Writer<W> {
+    encoding: Encoding,
+    inner: W,
+}
+
+impl<W: io::Write> io::Write for Writer<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.encoding == Encoding::Utf8 {
+            return self.inner.write(buf);
+        }
+
+        self.inner.write_all(&self.encoding.encode(Cow::Borrowed(buf)))?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use super::Encoding;
+
+    fn round_trip(label: &str, text: &str, encoded: &[u8]) {
+        let encoding = Encoding::from_label(label).unwrap();
+        assert_eq!(&*encoding.encode(Cow::Borrowed(text.as_bytes())), encoded);
+
+        // Feed the output one byte at a time to split multi-byte characters
+        let mut decoder = encoding.decoder();
+        let mut decoded = Vec::new();
+        for byte in encoded {
+            decoded.extend_from_slice(decoder.decode(&[*byte]));
+        }
+        assert_eq!(String::from_utf8(decoded).unwrap(), text);
+    }
+
+    #[test]
+    fn round_trip_latin1() {
+        round_trip("latin1", "\x1b[1mdéjà vu", b"\x1b[1md\xe9j\xe0 vu");
+    }
+
+    #[test]
+    fn round_trip_cp437() {
+        round_trip("cp437", "┌─┐ Ç½\u{a0}", b"\xda\xc4\xbf \x80\xab\xff");
+    }
+
+    #[test]
+    fn round_trip_euc_jp() {
+        round_trip("EUC-JP", "ls 日本語", b"ls \xc6\xfc\xcb\xdc\xb8\xec");
+    }
+
+    #[test]
+    fn c1_controls_are_single_bytes() {
+        for label in &["latin1", "cp437", "EUC-JP"] {
+            let encoding = Encoding::from_label(label).unwrap();
+            let encoded = encoding.encode(Cow::Borrowed("\u{9b}0n\u{90}1\u{9c}".as_bytes()));
+            assert_eq!(&*encoded, b"\x9b0n\x901\x9c");
+        }
+    }
+
+    #[test]
+    fn utf8_is_passed_through() {
+        let encoding = Encoding::from_label("UTF-8").unwrap();
+        let input = "日本語".as_bytes();
+        assert_eq!(encoding.decoder().decode(input), input);
+        match encoding.encode(Cow::Borrowed(input)) {
+            Cow::Borrowed(bytes) => assert_eq!(bytes, input),
+            Cow::Owned(_) => panic!("UTF-8 input was copied"),
+        }
+    }
+
+    #[test]
+    fn latin1_keeps_c1_controls() {
+        let mut decoder = Encoding::Latin1.decoder();
+        assert_eq!(decoder.decode(b"\x9b1m\xff"), b"\x9b1m\xc3\xbf");
+    }
+
+    #[test]
+    fn unmappable_characters_are_replaced() {
+        let input = Cow::Borrowed("a日b".as_bytes());
+        assert_eq!(&*Encoding::Latin1.encode(input), b"a?b");
+    }
+
+    #[test]
+    fn invalid_utf8_input_is_passed_on() {
+        let input = Cow::Borrowed(&b"\x1b[M \xe9\xff\xc3\xa9"[..]);
+        assert_eq!(&*Encoding::Latin1.encode(input), b"\x1b[M \xe9\xff\xe9");
+    }
+
+    #[test]
+    fn reject_unusable_labels() {
+        assert_eq!(Encoding::from_label("utf-16le"), None);
+        assert_eq!(Encoding::from_label("no such encoding"), None);
+        assert_eq!(Encoding::from_label("Latin1"), Some(Encoding::Latin1));
+    }
+}
@@ -2,8 +2,9 @@
 use std::borrow::Cow;
 use std::fs::File;
 use std::io::Write;
+use std::path::PathBuf;
 use std::sync::mpsc;
-use std::time::{Instant};
+use std::time::{Duration, Instant};
 
 use serde_json as json;
 use parking_lot::MutexGuard;
@@ -15,10 +16,12 @@ use cli::Options;
 use display::OnResize;
 use index::{Line, Column, Side, Point};
 use input::{self, MouseBinding, KeyBinding};
+use recorder::Recorder;
 use selection::Selection;
 use sync::FairMutex;
 use term::{Term, SizeInfo, TermMode};
-use util::limit;
+use tty::ForegroundProcess;
+use util::{limit, thread};
 use util::fmt::Red;
 use window::Window;
 
@@ -40,10 +43,17 @@ pub struct ActionContext<'a, N: 'a> {
     pub received_count: &'a mut usize,
     pub suppress_chars: &'a mut bool,
     pub last_modifiers: &'a mut ModifiersState,
+    pub alt_keys: &'a mut input::AltKeys,
+    pub foreground_process: &'a ForegroundProcess,
+    pub recorder: &'a mut Recorder,
+    pub paste_config: &'a config::PasteConfig,
+    pub pending_paste: &'a mut Option<String>,
 }
 
 impl<'a, N: Notify + 'a> input::ActionContext for ActionContext<'a, N> {
     fn write_to_pty<B: Into<Cow<'static, [u8]>>>(&mut self, val: B) {
+        let val = val.into();
+        self.recorder.record(&val);
         self.notifier.notify(val);
     }
 
@@ -77,6 +87,7 @@ impl<'a, N: Notify + 'a> input::ActionContext for ActionContext<'a, N> {
     }
 
     fn update_selection(&mut self, point: Point, side: Side) {
+        let (point, side) = self.terminal.selection_point(point, side);
         self.selection_modified = true;
         // Update selection if one exists
         if let Some(ref mut selection) = *self.selection {
@@ -85,15 +96,17 @@ impl<'a, N: Notify + 'a> input::ActionContext for ActionContext<'a, N> {
         }
 
         // Otherwise, start a regular selection
-        self.simple_selection(point, side);
+        *self.selection = Some(Selection::simple(point, side));
     }
 
     fn simple_selection(&mut self, point: Point, side: Side) {
+        let (point, side) = self.terminal.selection_point(point, side);
         *self.selection = Some(Selection::simple(point, side));
         self.selection_modified = true;
     }
 
     fn semantic_selection(&mut self, point: Point) {
+        let point = self.terminal.logical_point(point);
         *self.selection = Some(Selection::semantic(point, self.terminal));
         self.selection_modified = true;
     }
@@ -134,6 +147,59 @@ impl<'a, N: Notify + 'a> input::ActionContext for ActionContext<'a, N> {
     fn last_modifiers(&mut self) -> &mut ModifiersState {
         &mut self.last_modifiers
     }
+
+    #[inline]
+    fn alt_keys(&mut self) -> &mut input::AltKeys {
+        &mut self.alt_keys
+    }
+
+    fn foreground_process(&self) -> Option<String> {
+        self.foreground_process.name()
+    }
+
+    fn title(&self) -> Option<&str> {
+        self.terminal.title()
+    }
+
+    #[inline]
+    fn recorder(&mut self) -> &mut Recorder {
+        self.recorder
+    }
+
+    fn selection_text(&self) -> Option<String> {
+        self.selection.as_ref()
+            .and_then(|selection| selection.to_span(self.terminal))
+            .map(|span| self.terminal.string_from_selection(&span))
+    }
+
+    fn working_directory(&self) -> Option<&str> {
+        self.terminal.working_directory()
+    }
+
+    #[inline]
+    fn paste_config(&self) -> &config::PasteConfig {
+        self.paste_config
+    }
+
+    #[inline]
+    fn pending_paste(&mut self) -> &mut Option<String> {
+        self.pending_paste
+    }
+}
+
+/// Print the name and scancode of a pressed key
+///
+/// Helps with writing key bindings; the scancode identifies the physical key
+/// regardless of the keyboard layout.
+fn print_key(event: &Event) {
+    if let Event::WindowEvent { event: glutin::WindowEvent::KeyboardInput { input, .. }, .. } = *event {
+        if input.state == ElementState::Pressed {
+            match input.virtual_keycode {
+                Some(keycode) => println!("key: {:?}, scancode: {}", keycode, input.scancode),
+                None => println!("key: unknown, scancode: {}", input.scancode),
+            }
+        }
+    }
 }
 
 pub enum ClickState {
@@ -157,6 +223,7 @@ pub struct Mouse {
     pub column: Column,
     pub cell_side: Side,
     pub lines_scrolled: f32,
+    pub columns_scrolled: f32,
 }
 
 impl Default for Mouse {
@@ -174,6 +241,7 @@ impl Default for Mouse {
             column: Column(0),
             cell_side: Side::Left,
             lines_scrolled: 0.0,
+            columns_scrolled: 0.0,
         }
     }
 }
@@ -186,7 +254,13 @@ pub struct Processor<N> {
     key_bindings: Vec<KeyBinding>,
     mouse_bindings: Vec<MouseBinding>,
     mouse_config: config::Mouse,
+    alt_config: config::AltConfig,
+    paste_config: config::PasteConfig,
+    pending_paste: Option<String>,
+    chord: input::Chord,
+    chord_timeout: Duration,
     print_events: bool,
+    print_keys: bool,
     wait_for_event: bool,
     notifier: N,
     mouse: Mouse,
@@ -199,6 +273,9 @@ pub struct Processor<N> {
     received_count: usize,
     suppress_chars: bool,
     last_modifiers: ModifiersState,
+    alt_keys: input::AltKeys,
+    foreground_process: ForegroundProcess,
+    recorder: Recorder,
     pending_events: Vec<Event>,
 }
 
@@ -224,12 +301,19 @@ impl<N: Notify> Processor<N> {
         config: &Config,
         ref_test: bool,
         size_info: SizeInfo,
+        foreground_process: ForegroundProcess,
     ) -> Processor<N> {
         Processor {
             key_bindings: config.key_bindings().to_vec(),
             mouse_bindings: config.mouse_bindings().to_vec(),
             mouse_config: config.mouse().to_owned(),
+            alt_config: config.alt().to_owned(),
+            paste_config: config.paste().to_owned(),
+            pending_paste: None,
+            chord: Default::default(),
+            chord_timeout: config.chord_timeout(),
             print_events: options.print_events,
+            print_keys: options.print_keys,
             wait_for_event: true,
             notifier,
             resize_tx,
@@ -242,6 +326,9 @@ impl<N: Notify> Processor<N> {
             received_count: 0,
             suppress_chars: false,
             last_modifiers: Default::default(),
+            alt_keys: Default::default(),
+            foreground_process,
+            recorder: Recorder::new(Recorder::default_path()),
             pending_events: Vec::with_capacity(4),
         }
     }
@@ -256,6 +343,7 @@ impl<N: Notify> Processor<N> {
         resize_tx: &mpsc::Sender<(u32, u32)>,
         hide_cursor: &mut bool,
         window_is_focused: &mut bool,
+        dropped_files: &mut Vec<PathBuf>,
     ) {
         match event {
             // Pass on device events
@@ -291,8 +379,9 @@ impl<N: Notify> Processor<N> {
                         processor.ctx.terminal.dirty = true;
                     },
                     KeyboardInput { input, .. } => {
-                        let glutin::KeyboardInput { state, virtual_keycode, modifiers, .. } = input;
-                        processor.process_key(state, virtual_keycode, &modifiers);
+                        let glutin::KeyboardInput { state, virtual_keycode, scancode, modifiers } = input;
+                        let key = input::KeyPress { keycode: virtual_keycode, scancode };
+                        processor.process_key(state, key, &modifiers);
                         if state == ElementState::Pressed {
                             // Hide cursor while typing
                             *hide_cursor = true;
@@ -339,6 +428,10 @@ impl<N: Notify> Processor<N> {
 
                         processor.on_focus_change(is_focused);
                     }
+                    DroppedFile(path) => {
+                        // Pasted together once all files of the drop arrived
+                        dropped_files.push(path);
+                    },
                     _ => (),
                 }
             },
@@ -368,6 +461,7 @@ impl<N: Notify> Processor<N> {
             let mut processor: input::Processor<ActionContext<N>>;
 
             let print_events = self.print_events;
+            let print_keys = self.print_keys;
 
             let ref_test = self.ref_test;
             let resize_tx = &self.resize_tx;
@@ -395,16 +489,28 @@ impl<N: Notify> Processor<N> {
                 received_count: &mut self.received_count,
                 suppress_chars: &mut self.suppress_chars,
                 last_modifiers: &mut self.last_modifiers,
+                alt_keys: &mut self.alt_keys,
+                foreground_process: &self.foreground_process,
+                recorder: &mut self.recorder,
+                paste_config: &self.paste_config,
+                pending_paste: &mut self.pending_paste,
             };
 
+            let pending_chord = self.chord.describe();
+            let paste_was_pending = context.pending_paste.is_some();
+
             processor = input::Processor {
                 ctx: context,
                 mouse_config: &self.mouse_config,
+                alt_config: &self.alt_config,
+                chord: &mut self.chord,
+                chord_timeout: self.chord_timeout,
                 key_bindings: &self.key_bindings[..],
                 mouse_bindings: &self.mouse_bindings[..],
             };
 
             let mut window_is_focused = window.is_focused;
+            let mut dropped_files = Vec::new();
 
             // Scope needed to that hide_cursor isn't borrowed after the scope
             // ends.
@@ -414,6 +520,9 @@ impl<N: Notify> Processor<N> {
                     if print_events {
                         println!("glutin event: {:?}", event);
                     }
+                    if print_keys {
+                        print_key(&event);
+                    }
                     Processor::handle_event(
                         &mut processor,
                         event,
@@ -421,6 +530,7 @@ impl<N: Notify> Processor<N> {
                         resize_tx,
                         hide_cursor,
                         &mut window_is_focused,
+                        &mut dropped_files,
                     );
                 };
 
@@ -431,10 +541,36 @@ impl<N: Notify> Processor<N> {
                 window.poll_events(process);
             }
 
+            if !dropped_files.is_empty() {
+                processor.drop_files(&dropped_files);
+            }
+
             if self.hide_cursor_when_typing {
                 window.set_cursor_visible(!self.hide_cursor);
             }
 
+            // Update the chord indicator when a key is added to the chord or
+            // it's finished. While it's pending, wake up once it times out so
+            // it's abandoned without waiting for more input.
+            processor.check_chord_timeout();
+            if processor.chord.describe() != pending_chord {
+                processor.ctx.terminal.dirty = true;
+
+                if processor.chord.is_pending() {
+                    let proxy = window.create_window_proxy();
+                    let timeout = processor.chord_timeout;
+                    thread::spawn_named("chord timeout", move || {
+                        thread::sleep(timeout);
+                        proxy.wakeup_event_loop();
+                    });
+                }
+            }
+
+            // Show or hide the paste confirmation
+            if paste_was_pending != processor.ctx.pending_paste.is_some() {
+                processor.ctx.terminal.dirty = true;
+            }
+
             window.is_focused = window_is_focused;
 
             if processor.ctx.selection_modified {
@@ -447,9 +583,30 @@ impl<N: Notify> Processor<N> {
         terminal
     }
 
+    /// Description of the keys of the pending chord binding
+    pub fn pending_chord(&self) -> Option<String> {
+        self.chord.describe()
+    }
+
+    /// Confirmation prompt of a paste waiting to be written
+    pub fn paste_prompt(&self) -> Option<String> {
+        self.pending_paste.as_ref().map(|contents| input::paste_prompt(contents))
+    }
+
+    /// Write the input stored in a register to the pty
+    pub fn replay_macro(&mut self, register: &str, count: usize) {
+        match self.recorder.replay(register, count) {
+            Some(input) => self.notifier.notify(input),
+            None => warn!("register {} is empty", register),
+        }
+    }
+
     pub fn update_config(&mut self, config: &Config) {
+        self.chord_timeout = config.chord_timeout();
         self.key_bindings = config.key_bindings().to_vec();
         self.mouse_bindings = config.mouse_bindings().to_vec();
         self.mouse_config = config.mouse().to_owned();
+        self.alt_config = config.alt().to_owned();
+        self.paste_config = config.paste().to_owned();
     }
 }
@@ -14,8 +14,10 @@ use mio_more::channel::{self, Sender, Receiver};
 
 use ansi;
 use display;
+use encoding::{Decoder, Encoding};
 use event;
 use term::Term;
+use tty;
 use util::thread;
 use sync::FairMutex;
 
@@ -25,6 +27,9 @@ pub enum Msg {
     /// Data that should be written to the pty
     Input(Cow<'static, [u8]>),
 
+    /// Change the encoding of the pty stream
+    Encoding(Encoding),
+
     /// Indicates that the `EventLoop` should shut down, as Alacritty is shutting down
     Shutdown
 }
@@ -40,6 +45,8 @@ pub struct EventLoop<Io> {
     tx: Sender<Msg>,
     terminal: Arc<FairMutex<Term>>,
     display: display::Notifier,
+    encoding: Encoding,
+    decoder: Decoder,
     ref_test: bool,
 }
 
@@ -174,6 +181,7 @@ impl<Io> EventLoop<Io>
         terminal: Arc<FairMutex<Term>>,
         display: display::Notifier,
         pty: Io,
+        encoding: Encoding,
         ref_test: bool,
     ) -> EventLoop<Io> {
         let (tx, rx) = channel::channel();
@@ -184,6 +192,8 @@ impl<Io> EventLoop<Io>
             rx,
             terminal,
             display,
+            encoding,
+            decoder: encoding.decoder(),
             ref_test,
         }
     }
@@ -196,13 +206,17 @@ impl<Io> EventLoop<Io>
     //
     // Returns a `DrainResult` indicating the result of receiving from the channel
     //
-    fn drain_recv_channel(&self, state: &mut State) -> DrainResult {
+    fn drain_recv_channel(&mut self, state: &mut State) -> DrainResult {
         let mut received_item = false;
         while let Ok(msg) = self.rx.try_recv() {
             received_item = true;
             match msg {
                 Msg::Input(input) => {
-                    state.write_list.push_back(input);
+                    state.write_list.push_back(self.encoding.encode(input));
+                },
+                Msg::Encoding(encoding) => {
+                    self.encoding = encoding;
+                    self.decoder = encoding.decoder();
                 },
                 Msg::Shutdown => {
                     return DrainResult::Shutdown;
@@ -262,10 +276,12 @@ impl<Io> EventLoop<Io>
                     // Record bytes read; used to limit time spent in pty_read.
                     processed += got;
 
+                    let bytes = self.decoder.decode(&buf[..got]);
+
                     // Send a copy of bytes read to a subscriber. Used for
                     // example with ref test recording.
                     writer = writer.map(|w| {
-                        w.write_all(&buf[..got]).unwrap();
+                        w.write_all(bytes).unwrap();
                         w
                     });
 
@@ -277,9 +293,18 @@ impl<Io> EventLoop<Io>
                     }
                     let terminal = terminal.as_mut().unwrap();
 
-                    // Run the parser
-                    for byte in &buf[..got] {
-                        state.parser.advance(&mut **terminal, *byte, &mut self.pty);
+                    // Run the parser; replies are sent in the application's encoding
+                    {
+                        let mut pty = self.encoding.writer(&mut self.pty);
+                        for byte in bytes {
+                            state.parser.advance(&mut **terminal, *byte, &mut pty);
+                        }
+                    }
+
+                    // Showing or hiding the status line changes the size the
+                    // application sees; let it know right away
+                    if let Some(size) = terminal.get_next_pty_size() {
+                        tty::resize(self.pty.as_raw_fd(), &&size);
                     }
 
                     // Exit if we've processed enough bytes
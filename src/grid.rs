@@ -547,6 +547,21 @@ clear_region_impl!(Range<index::Line>);
 clear_region_impl!(RangeTo<index::Line>);
 clear_region_impl!(RangeFrom<index::Line>);
 
+impl<T> ClearRegion<(Range<index::Line>, Range<index::Column>), T> for Grid<T> {
+    fn clear_region<F: Fn(&mut T)>(
+        &mut self,
+        region: (Range<index::Line>, Range<index::Column>),
+        func: F
+    ) {
+        let (lines, cols) = region;
+        for row in self[lines].iter_mut() {
+            for cell in &mut row[cols.clone()] {
+                func(cell);
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::{Grid, BidirectionalIterator};
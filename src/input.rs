@@ -20,16 +20,19 @@
 //! determine what to do when a non-modifier key is pressed.
 use std::borrow::Cow;
 use std::mem;
+use std::path::PathBuf;
 use std::process::Command;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use std::os::unix::process::CommandExt;
 
 use copypasta::{Clipboard, Load, Buffer};
 use glutin::{ElementState, VirtualKeyCode, MouseButton, TouchPhase, MouseScrollDelta, ModifiersState};
 
-use config;
+use config::{self, AltSend, FileQuoting};
 use event::{ClickState, Mouse};
 use index::{Line, Column, Side, Point};
+use recorder::Recorder;
+use snippet::{self, Placeholder, Snippet};
 use term::SizeInfo;
 use term::mode::TermMode;
 use util::fmt::Red;
@@ -44,9 +47,94 @@ pub struct Processor<'a, A: 'a> {
     pub key_bindings: &'a [KeyBinding],
     pub mouse_bindings: &'a [MouseBinding],
     pub mouse_config: &'a config::Mouse,
+    pub alt_config: &'a config::AltConfig,
+    pub chord: &'a mut Chord,
+    pub chord_timeout: Duration,
     pub ctx: A,
 }
 
+/// Alt keys held down, as far as glutin reports them
+#[derive(Debug, Default, Clone, Copy)]
+pub struct AltKeys {
+    pub left: bool,
+    pub right: bool,
+}
+
+/// State of a partially entered chord binding
+#[derive(Debug, Default)]
+pub struct Chord {
+    /// Keys of the chord pressed so far
+    keys: Vec<(KeyPress, ModifiersState)>,
+
+    /// Input received while the chord is pending; replayed if it's abandoned
+    input: Vec<ChordInput>,
+
+    /// Time the last key of the chord was pressed
+    last_key: Option<Instant>,
+}
+
+#[derive(Debug)]
+enum ChordInput {
+    Key(KeyPress, ModifiersState),
+    Char(char),
+}
+
+impl Chord {
+    #[inline]
+    pub fn is_pending(&self) -> bool {
+        !self.keys.is_empty()
+    }
+
+    /// Describe the keys pressed so far, like `Ctrl+A`
+    pub fn describe(&self) -> Option<String> {
+        if !self.is_pending() {
+            return None;
+        }
+
+        let keys = self.keys.iter()
+            .map(|&(key, mods)| {
+                let mut stroke = String::new();
+                if mods.ctrl {
+                    stroke.push_str("Ctrl+");
+                }
+                if mods.alt {
+                    stroke.push_str("Alt+");
+                }
+                if mods.shift {
+                    stroke.push_str("Shift+");
+                }
+                if mods.logo {
+                    stroke.push_str("Super+");
+                }
+                match key.keycode {
+                    Some(keycode) => stroke.push_str(&format!("{:?}", keycode)),
+                    None => stroke.push_str(&format!("#{}", key.scancode)),
+                }
+                stroke
+            })
+            .collect::<Vec<_>>();
+
+        Some(keys.join(" "))
+    }
+
+    fn push(&mut self, key: KeyPress, mods: ModifiersState) {
+        self.keys.push((key, mods));
+        self.input.push(ChordInput::Key(key, mods));
+        self.last_key = Some(Instant::now());
+    }
+
+    fn is_expired(&self, timeout: Duration) -> bool {
+        self.last_key.map_or(false, |last_key| last_key.elapsed() >= timeout)
+    }
+
+    /// Reset the chord, returning the input received while it was pending
+    fn take_input(&mut self) -> Vec<ChordInput> {
+        self.keys.clear();
+        self.last_key = None;
+        mem::replace(&mut self.input, Vec::new())
+    }
+}
+
 pub trait ActionContext {
     fn write_to_pty<B: Into<Cow<'static, [u8]>>>(&mut self, B);
     fn terminal_mode(&self) -> TermMode;
@@ -62,6 +150,14 @@ pub trait ActionContext {
     fn received_count(&mut self) -> &mut usize;
     fn suppress_chars(&mut self) -> &mut bool;
     fn last_modifiers(&mut self) -> &mut ModifiersState;
+    fn alt_keys(&mut self) -> &mut AltKeys;
+    fn recorder(&mut self) -> &mut Recorder;
+    fn selection_text(&self) -> Option<String>;
+    fn working_directory(&self) -> Option<&str>;
+    fn paste_config(&self) -> &config::PasteConfig;
+    fn pending_paste(&mut self) -> &mut Option<String>;
+    fn foreground_process(&self) -> Option<String>;
+    fn title(&self) -> Option<&str>;
     fn change_font_size(&mut self, delta: i8);
     fn reset_font_size(&mut self);
 }
@@ -83,6 +179,14 @@ pub struct Binding<T> {
     /// excluded terminal modes where the binding won't be activated
     pub notmode: TermMode,
 
+    /// Keys which have to be pressed, in order, before the trigger
+    ///
+    /// Only key bindings can be chords.
+    pub leader: Vec<(T, ModifiersState)>,
+
+    /// Foreground process or title required to activate binding
+    pub condition: Option<Condition>,
+
     /// This property is used as part of the trigger detection code.
     ///
     /// For example, this might be a key like "G", or a mouse button.
@@ -90,19 +194,92 @@ pub struct Binding<T> {
 }
 
 /// Bindings that are triggered by a keyboard key
-pub type KeyBinding = Binding<VirtualKeyCode>;
+pub type KeyBinding = Binding<KeyTrigger>;
+
+/// Key which can trigger a binding
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyTrigger {
+    /// Key with this meaning in the current keyboard layout
+    Keycode(VirtualKeyCode),
+
+    /// Key at this physical position, whatever the keyboard layout
+    Scancode(u32),
+}
+
+/// Key pressed on the keyboard
+#[derive(Debug, Clone, Copy)]
+pub struct KeyPress {
+    /// Meaning of the key in the current layout, if glutin knows it
+    pub keycode: Option<VirtualKeyCode>,
 
-/// Bindings that are triggered by a mouse button
-pub type MouseBinding = Binding<MouseButton>;
+    /// Physical position of the key
+    pub scancode: u32,
+}
 
-impl<T: Eq> Binding<T> {
+impl PartialEq<KeyPress> for KeyTrigger {
+    fn eq(&self, key: &KeyPress) -> bool {
+        match *self {
+            KeyTrigger::Keycode(keycode) => key.keycode == Some(keycode),
+            KeyTrigger::Scancode(scancode) => key.scancode == scancode,
+        }
+    }
+}
+
+/// Bindings that are triggered by a mouse button or the wheel
+pub type MouseBinding = Binding<MouseTrigger>;
+
+/// Mouse input which can trigger a binding
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MouseTrigger {
+    /// Press of a button
+    Button(MouseButton),
+
+    /// Second press of a button within the double click threshold
+    DoubleClick(MouseButton),
+
+    /// Scrolling by one line
+    WheelUp,
+    WheelDown,
+    WheelLeft,
+    WheelRight,
+}
+
+/// Restricts a binding to what's running in the terminal
+///
+/// The foreground process is only looked up once a binding with a process
+/// condition matches everything else.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Condition {
+    /// Name of the foreground process, or any other process if negated
+    Process { name: String, negated: bool },
+
+    /// Text contained in the window title, or absent from it if negated
+    Title { text: String, negated: bool },
+}
+
+impl Condition {
+    fn matches<A: ActionContext>(&self, ctx: &A) -> bool {
+        match *self {
+            Condition::Process { ref name, negated } => {
+                ctx.foreground_process().map_or(false, |process| process == *name) != negated
+            },
+            Condition::Title { ref text, negated } => {
+                ctx.title().map_or(false, |title| title.contains(&**text)) != negated
+            },
+        }
+    }
+}
+
+impl<T> Binding<T> {
     #[inline]
-    fn is_triggered_by(
+    fn is_triggered_by<I>(
         &self,
         mode: TermMode,
         mods: &ModifiersState,
-        input: &T
-    ) -> bool {
+        input: &I
+    ) -> bool
+        where T: PartialEq<I>
+    {
         // Check input first since bindings are stored in one big list. This is
         // the most likely item to fail so prioritizing it here allows more
         // checks to be short circuited.
@@ -130,15 +307,28 @@ impl<T> Binding<T> {
         self.notmode.is_empty() || !mode.intersects(self.notmode)
     }
 
+    #[inline]
+    fn condition_matches<A: ActionContext>(&self, ctx: &A) -> bool {
+        self.condition.as_ref().map_or(true, |condition| condition.matches(ctx))
+    }
+
     /// Check that two mods descriptions for equivalence
     ///
     /// Optimized to use single check instead of four (one per modifier)
     #[inline]
     fn mods_match(&self, mods: &ModifiersState) -> bool {
-        debug_assert!(4 == mem::size_of::<ModifiersState>());
-        unsafe {
-            mem::transmute_copy::<_, u32>(&self.mods) == mem::transmute_copy::<_, u32>(mods)
-        }
+        mods_eq(&self.mods, mods)
+    }
+}
+
+/// Check that two mods descriptions for equivalence
+///
+/// Optimized to use single check instead of four (one per modifier)
+#[inline]
+fn mods_eq(a: &ModifiersState, b: &ModifiersState) -> bool {
+    debug_assert!(4 == mem::size_of::<ModifiersState>());
+    unsafe {
+        mem::transmute_copy::<_, u32>(a) == mem::transmute_copy::<_, u32>(b)
     }
 }
 
@@ -147,6 +337,9 @@ pub enum Action {
     /// Write an escape sequence
     Esc(String),
 
+    /// Write text with placeholders filled in
+    Snippet(Snippet),
+
     /// Paste contents of system clipboard
     Paste,
 
@@ -168,6 +361,12 @@ pub enum Action {
     /// Run given command
     Command(String, Vec<String>),
 
+    /// Start recording input into a register, or stop the running recording
+    RecordMacro(String),
+
+    /// Write the input stored in a register the given number of times
+    ReplayMacro(String, usize),
+
     /// Quits Alacritty.
     Quit,
 }
@@ -179,13 +378,36 @@ impl Action {
             Action::Esc(ref s) => {
                 ctx.write_to_pty(s.clone().into_bytes())
             },
+            Action::Snippet(ref snippet) => {
+                let text = snippet.expand(|placeholder| match *placeholder {
+                    Placeholder::Selection => ctx.selection_text().unwrap_or_default(),
+                    Placeholder::Clipboard => {
+                        Clipboard::new()
+                            .and_then(|clipboard| clipboard.load_primary())
+                            .unwrap_or_else(|err| {
+                                warn!("Error loading data from clipboard. {}", Red(err));
+                                String::new()
+                            })
+                    },
+                    Placeholder::WorkingDirectory => {
+                        ctx.working_directory().map(String::from).unwrap_or_default()
+                    },
+                    Placeholder::Date(ref format) => snippet::format_date(format),
+                });
+
+                if snippet.paste() {
+                    paste(ctx, text);
+                } else {
+                    ctx.write_to_pty(text.into_bytes());
+                }
+            },
             Action::Copy => {
                 ctx.copy_selection(Buffer::Primary);
             },
             Action::Paste => {
                 Clipboard::new()
                     .and_then(|clipboard| clipboard.load_primary() )
-                    .map(|contents| { self.paste(ctx, contents) })
+                    .map(|contents| { request_paste(ctx, contents) })
                     .unwrap_or_else(|err| {
                         eprintln!("Error loading data from clipboard. {}", Red(err));
                     });
@@ -193,7 +415,7 @@ impl Action {
             Action::PasteSelection => {
                 Clipboard::new()
                     .and_then(|clipboard| clipboard.load_selection() )
-                    .map(|contents| { self.paste(ctx, contents) })
+                    .map(|contents| { request_paste(ctx, contents) })
                     .unwrap_or_else(|err| {
                         warn!("Error loading data from clipboard. {}", Red(err));
                     });
@@ -218,6 +440,20 @@ impl Action {
                     },
                 }
             },
+            Action::RecordMacro(ref register) => {
+                let recorder = ctx.recorder();
+                if recorder.recording().is_some() {
+                    recorder.stop();
+                } else {
+                    recorder.start(register);
+                }
+            },
+            Action::ReplayMacro(ref register, count) => {
+                match ctx.recorder().replay(register, count) {
+                    Some(input) => ctx.write_to_pty(input),
+                    None => warn!("register {} is empty", register),
+                }
+            },
             Action::Quit => {
                 // FIXME should do a more graceful shutdown
                 ::std::process::exit(0);
@@ -233,22 +469,116 @@ impl Action {
             }
         }
     }
+}
 
-    fn paste<A: ActionContext>(&self, ctx: &mut A, contents: String) {
-        if ctx.terminal_mode().contains(TermMode::BRACKETED_PASTE) {
-            ctx.write_to_pty(&b"\x1b[200~"[..]);
-            ctx.write_to_pty(contents.into_bytes());
-            ctx.write_to_pty(&b"\x1b[201~"[..]);
-        } else {
-            // In non-bracketed (ie: normal) mode, terminal applications cannot distinguish
-            // pasted data from keystrokes.
-            // In theory, we should construct the keystrokes needed to produce the data we are
-            // pasting... since that's neither practical nor sensible (and probably an impossible
-            // task to solve in a general way), we'll just replace line breaks (windows and unix
-            // style) with a singe carriage return (\r, which is what the Enter key produces).
-            ctx.write_to_pty(contents.replace("\r\n","\r").replace("\n","\r").into_bytes());
-        }
+/// Write pasted text to the pty, bracketed if the application asks for it
+fn paste<A: ActionContext>(ctx: &mut A, contents: String) {
+    let contents = sanitize_paste(contents, ctx.paste_config().sanitize());
+    if ctx.terminal_mode().contains(TermMode::BRACKETED_PASTE) {
+        ctx.write_to_pty(&b"\x1b[200~"[..]);
+        ctx.write_to_pty(contents.into_bytes());
+        ctx.write_to_pty(&b"\x1b[201~"[..]);
+    } else {
+        // In non-bracketed (ie: normal) mode, terminal applications cannot distinguish
+        // pasted data from keystrokes.
+        // In theory, we should construct the keystrokes needed to produce the data we are
+        // pasting... since that's neither practical nor sensible (and probably an impossible
+        // task to solve in a general way), we'll just replace line breaks (windows and unix
+        // style) with a singe carriage return (\r, which is what the Enter key produces).
+        ctx.write_to_pty(contents.replace("\r\n","\r").replace("\n","\r").into_bytes());
+    }
+}
+
+/// Paste text, or ask for confirmation first if it looks risky
+fn request_paste<A: ActionContext>(ctx: &mut A, contents: String) {
+    let needs_confirmation = {
+        let config = ctx.paste_config();
+        let multiline = config.confirm_multiline()
+            && !ctx.terminal_mode().contains(TermMode::BRACKETED_PASTE)
+            && contents.contains(|c| c == '\n' || c == '\r');
+        let large = config.confirm_size() != 0 && contents.len() > config.confirm_size();
+        multiline || large
+    };
+
+    if needs_confirmation {
+        *ctx.pending_paste() = Some(contents);
+    } else {
+        paste(ctx, contents);
+    }
+}
+
+/// Remove anything from pasted text which lets it escape the paste
+///
+/// The end of a bracketed paste is always removed; with `control` set, so
+/// are all control characters other than tabs and line breaks.
+fn sanitize_paste(contents: String, control: bool) -> String {
+    let mut contents = if control {
+        contents.chars()
+            .filter(|&c| !c.is_control() || c == '\t' || c == '\n' || c == '\r')
+            .collect()
+    } else {
+        contents
+    };
+
+    // Removing one end sequence may join the parts of another
+    while contents.contains("\x1b[201~") || contents.contains("\u{9b}201~") {
+        contents = contents.replace("\x1b[201~", "").replace("\u{9b}201~", "");
     }
+
+    contents
+}
+
+/// Quote a file path for the shell
+///
+/// Returns `None` for paths with control characters; a newline would end the
+/// command line no matter how it's quoted.
+fn quote_path(path: &str, quoting: FileQuoting) -> Option<String> {
+    if path.chars().any(char::is_control) {
+        return None;
+    }
+
+    let mut quoted = String::with_capacity(path.len() + 2);
+    match quoting {
+        FileQuoting::Single => {
+            quoted.push('\'');
+            quoted.push_str(&path.replace('\'', "'\\''"));
+            quoted.push('\'');
+        },
+        FileQuoting::Double => {
+            quoted.push('"');
+            for c in path.chars() {
+                match c {
+                    '"' | '$' | '`' | '\\' => quoted.push('\\'),
+                    // History expansion happens inside double quotes too
+                    '!' => {
+                        quoted.push_str("\"\\!\"");
+                        continue;
+                    },
+                    _ => (),
+                }
+                quoted.push(c);
+            }
+            quoted.push('"');
+        },
+        FileQuoting::Backslash => {
+            for c in path.chars() {
+                if !c.is_alphanumeric() && !"_-.,/:@%+=".contains(c) {
+                    quoted.push('\\');
+                }
+                quoted.push(c);
+            }
+        },
+    }
+    Some(quoted)
+}
+
+/// Prompt shown while a paste is waiting for confirmation
+pub fn paste_prompt(contents: &str) -> String {
+    format!(
+        "Paste {} lines ({} bytes)? Enter to paste, Esc to cancel",
+        contents.lines().count(),
+        contents.len(),
+    )
 }
 
 impl From<&'static str> for Action {
@@ -264,7 +594,7 @@ impl<'a, A: ActionContext + 'a> Processor<'a, A> {
         self.ctx.mouse_mut().y = y;
 
         let size_info = self.ctx.size_info();
-        if let Some(point) = size_info.pixels_to_coords(x as usize, y as usize) {
+        if let Some(point) = self.ctx.mouse_coords() {
             let prev_line = mem::replace(&mut self.ctx.mouse_mut().line, point.line);
             let prev_col = mem::replace(&mut self.ctx.mouse_mut().column, point.col);
 
@@ -379,7 +709,10 @@ impl<'a, A: ActionContext + 'a> Processor<'a, A> {
 
         self.ctx.mouse_mut().click_state = match self.ctx.mouse_mut().click_state {
             ClickState::Click if elapsed < self.mouse_config.double_click.threshold => {
-                self.on_mouse_double_click();
+                // A double click binding replaces the semantic selection
+                if !self.process_mouse_bindings(&modifiers, MouseTrigger::DoubleClick(button)) {
+                    self.on_mouse_double_click();
+                }
                 ClickState::DoubleClick
             },
             ClickState::DoubleClick if elapsed < self.mouse_config.triple_click.threshold => {
@@ -423,25 +756,33 @@ impl<'a, A: ActionContext + 'a> Processor<'a, A> {
     }
 
     pub fn on_mouse_wheel(&mut self, delta: MouseScrollDelta, phase: TouchPhase, modifiers: ModifiersState) {
-        let mouse_modes = TermMode::MOUSE_REPORT_CLICK | TermMode::MOUSE_DRAG | TermMode::MOUSE_MOTION;
-        if !self.ctx.terminal_mode().intersects(mouse_modes | TermMode::ALT_SCREEN) {
-            return;
-        }
-
         match delta {
-            MouseScrollDelta::LineDelta(_columns, lines) => {
+            MouseScrollDelta::LineDelta(columns, lines) => {
                 let to_scroll = self.ctx.mouse_mut().lines_scrolled + lines;
-                let code = if to_scroll > 0.0 {
-                    64
+                let trigger = if to_scroll > 0.0 {
+                    MouseTrigger::WheelUp
                 } else {
-                    65
+                    MouseTrigger::WheelDown
                 };
 
                 for _ in 0..(to_scroll.abs() as usize) {
-                    self.scroll_terminal(code, modifiers)
+                    self.on_wheel_step(trigger, modifiers)
                 }
 
                 self.ctx.mouse_mut().lines_scrolled = to_scroll % 1.0;
+
+                let to_scroll = self.ctx.mouse_mut().columns_scrolled + columns;
+                let trigger = if to_scroll > 0.0 {
+                    MouseTrigger::WheelRight
+                } else {
+                    MouseTrigger::WheelLeft
+                };
+
+                for _ in 0..(to_scroll.abs() as usize) {
+                    self.on_wheel_step(trigger, modifiers)
+                }
+
+                self.ctx.mouse_mut().columns_scrolled = to_scroll % 1.0;
             },
             MouseScrollDelta::PixelDelta(_x, y) => {
                 match phase {
@@ -454,15 +795,15 @@ impl<'a, A: ActionContext + 'a> Processor<'a, A> {
                         let height = self.ctx.size_info().cell_height as i32;
 
                         while self.ctx.mouse_mut().scroll_px.abs() >= height {
-                            let code = if self.ctx.mouse_mut().scroll_px > 0 {
+                            let trigger = if self.ctx.mouse_mut().scroll_px > 0 {
                                 self.ctx.mouse_mut().scroll_px -= height;
-                                64
+                                MouseTrigger::WheelUp
                             } else {
                                 self.ctx.mouse_mut().scroll_px += height;
-                                65
+                                MouseTrigger::WheelDown
                             };
 
-                            self.scroll_terminal(code, modifiers)
+                            self.on_wheel_step(trigger, modifiers)
                         }
                     },
                     _ => (),
@@ -471,14 +812,34 @@ impl<'a, A: ActionContext + 'a> Processor<'a, A> {
         }
     }
 
+    /// Run the binding of one wheel step, or scroll the terminal if there's none
+    fn on_wheel_step(&mut self, trigger: MouseTrigger, modifiers: ModifiersState) {
+        if self.process_mouse_bindings(&modifiers, trigger) {
+            return;
+        }
+
+        let code = match trigger {
+            MouseTrigger::WheelUp => 64,
+            MouseTrigger::WheelDown => 65,
+            MouseTrigger::WheelLeft => 66,
+            MouseTrigger::WheelRight => 67,
+            _ => return,
+        };
+
+        self.scroll_terminal(code, modifiers);
+    }
+
     fn scroll_terminal(&mut self, code: u8, modifiers: ModifiersState) {
-        debug_assert!(code == 64 || code == 65);
+        debug_assert!(code >= 64 && code <= 67);
 
         let faux_scrollback_lines = self.mouse_config.faux_scrollback_lines;
         let mouse_modes = TermMode::MOUSE_REPORT_CLICK | TermMode::MOUSE_DRAG | TermMode::MOUSE_MOTION;
         if self.ctx.terminal_mode().intersects(mouse_modes) {
             self.mouse_report(code, ElementState::Pressed, modifiers);
-        } else if faux_scrollback_lines > 0 {
+        } else if faux_scrollback_lines > 0 &&
+            code <= 65 &&
+            self.ctx.terminal_mode().contains(TermMode::ALT_SCREEN)
+        {
             // Faux scrolling
             let cmd = code + 1; // 64 + 1 = A, 65 + 1 = B
             let mut content = Vec::with_capacity(faux_scrollback_lines * 3);
@@ -526,7 +887,7 @@ impl<'a, A: ActionContext + 'a> Processor<'a, A> {
             return;
         }
 
-        self.process_mouse_bindings(&ModifiersState::default(), button);
+        self.process_mouse_bindings(&modifiers, MouseTrigger::Button(button));
     }
 
     /// Process key input
@@ -535,34 +896,98 @@ impl<'a, A: ActionContext + 'a> Processor<'a, A> {
     pub fn process_key(
         &mut self,
         state: ElementState,
-        key: Option<VirtualKeyCode>,
+        key: KeyPress,
         mods: &ModifiersState,
     ) {
-        match (key, state) {
-            (Some(key), ElementState::Pressed) => {
+        let pressed = state == ElementState::Pressed;
+        match key.keycode {
+            Some(VirtualKeyCode::LAlt) | Some(VirtualKeyCode::LMenu) => self.ctx.alt_keys().left = pressed,
+            Some(VirtualKeyCode::RAlt) | Some(VirtualKeyCode::RMenu) => self.ctx.alt_keys().right = pressed,
+            _ => (),
+        }
+
+        match state {
+            ElementState::Pressed => {
+                if self.ctx.pending_paste().is_some() {
+                    self.confirm_paste(key);
+                    *self.ctx.suppress_chars() = true;
+                    return;
+                }
+
+                if self.process_chord(mods, key) {
+                    *self.ctx.suppress_chars() = true;
+                    return;
+                }
+
                 *self.ctx.last_modifiers() = *mods;
                 *self.ctx.received_count() = 0;
                 *self.ctx.suppress_chars() = false;
 
-                if self.process_key_bindings(mods, key) {
+                if self.process_vt52_key(mods, key) || self.process_key_bindings(mods, key) {
                     *self.ctx.suppress_chars() = true;
                 }
             },
-            (_, ElementState::Released) => *self.ctx.suppress_chars() = false,
-            _ => ()
+            ElementState::Released => *self.ctx.suppress_chars() = false,
+        }
+    }
+
+    /// Paste the paths of files dropped on the window
+    pub fn drop_files(&mut self, paths: &[PathBuf]) {
+        let quoting = self.ctx.paste_config().file_quoting();
+        let quoted = paths.iter()
+            .filter_map(|path| {
+                let quoted = quote_path(&path.to_string_lossy(), quoting);
+                if quoted.is_none() {
+                    warn!("Not pasting {:?}; it contains control characters", path);
+                }
+                quoted
+            })
+            .collect::<Vec<_>>();
+
+        if !quoted.is_empty() {
+            paste(&mut self.ctx, quoted.join(" "));
+        }
+    }
+
+    /// Answer the confirmation of a pending paste
+    ///
+    /// Other keys are ignored until the paste is either confirmed or
+    /// cancelled.
+    fn confirm_paste(&mut self, key: KeyPress) {
+        match key.keycode {
+            Some(VirtualKeyCode::Return) | Some(VirtualKeyCode::NumpadEnter) | Some(VirtualKeyCode::Y) => {
+                let contents = self.ctx.pending_paste().take();
+                if let Some(contents) = contents {
+                    paste(&mut self.ctx, contents);
+                }
+            },
+            Some(VirtualKeyCode::Escape) | Some(VirtualKeyCode::N) => {
+                *self.ctx.pending_paste() = None;
+            },
+            _ => (),
         }
     }
 
     /// Process a received character
     pub fn received_char(&mut self, c: char) {
+        if self.chord.is_pending() {
+            self.chord.input.push(ChordInput::Char(c));
+            return;
+        }
+
         if !*self.ctx.suppress_chars() {
             self.ctx.clear_selection();
 
-            let utf8_len = c.len_utf8();
-            if *self.ctx.received_count() == 0 && self.ctx.last_modifiers().alt && utf8_len == 1 {
-                self.ctx.write_to_pty(b"\x1b".to_vec());
+            let mut c = c;
+            if *self.ctx.received_count() == 0 && c.len_utf8() == 1 && self.alt_is_meta() {
+                match self.alt_config.send() {
+                    AltSend::Escape => self.ctx.write_to_pty(b"\x1b".to_vec()),
+                    AltSend::EightBit => c = char::from(c as u8 | 0x80),
+                    AltSend::None => (),
+                }
             }
 
+            let utf8_len = c.len_utf8();
             let mut bytes = Vec::with_capacity(utf8_len);
             unsafe {
                 bytes.set_len(utf8_len);
@@ -575,15 +1000,33 @@ impl<'a, A: ActionContext + 'a> Processor<'a, A> {
         }
     }
 
+    /// Check if the Alt key held while typing acts as meta
+    fn alt_is_meta(&mut self) -> bool {
+        if !self.ctx.last_modifiers().alt {
+            return false;
+        }
+
+        let keys = *self.ctx.alt_keys();
+        if !keys.left && !keys.right {
+            // Glutin didn't report which Alt key is held
+            return self.alt_config.left();
+        }
+
+        (keys.left && self.alt_config.left()) || (keys.right && self.alt_config.right())
+    }
+
     /// Attempts to find a binding and execute its action
     ///
     /// The provided mode, mods, and key must match what is allowed by a binding
     /// for its action to be executed.
     ///
     /// Returns true if an action is executed.
-    fn process_key_bindings(&mut self, mods: &ModifiersState, key: VirtualKeyCode) -> bool {
+    fn process_key_bindings(&mut self, mods: &ModifiersState, key: KeyPress) -> bool {
         for binding in self.key_bindings {
-            if binding.is_triggered_by(self.ctx.terminal_mode(), mods, &key) {
+            if binding.leader.is_empty() &&
+                binding.is_triggered_by(self.ctx.terminal_mode(), mods, &key) &&
+                binding.condition_matches(&self.ctx)
+            {
                 // binding was triggered; run the action
                 binding.execute(&mut self.ctx);
                 return true;
@@ -593,15 +1036,113 @@ impl<'a, A: ActionContext + 'a> Processor<'a, A> {
         false
     }
 
+    /// Start, continue or complete a chord binding
+    ///
+    /// Returns true if the key was consumed by a chord. A key which doesn't
+    /// continue the pending chord abandons it.
+    fn process_chord(&mut self, mods: &ModifiersState, key: KeyPress) -> bool {
+        self.check_chord_timeout();
+
+        let mode = self.ctx.terminal_mode();
+        let pressed = self.chord.keys.len();
+        let mut continued = false;
+
+        for binding in self.key_bindings {
+            if binding.leader.is_empty() ||
+                binding.leader.len() < pressed ||
+                !binding.mode_matches(&mode) ||
+                !binding.not_mode_matches(&mode)
+            {
+                continue;
+            }
+
+            let leader_matches = binding.leader.iter()
+                .zip(&self.chord.keys)
+                .all(|(a, b)| a.0 == b.0 && mods_eq(&a.1, &b.1));
+            if !leader_matches {
+                continue;
+            }
+
+            if binding.leader.len() > pressed {
+                let (next_key, next_mods) = binding.leader[pressed];
+                continued |= next_key == key && mods_eq(&next_mods, mods);
+            } else if binding.trigger == key &&
+                binding.mods_match(mods) &&
+                binding.condition_matches(&self.ctx)
+            {
+                // chord was completed; run the action
+                self.chord.take_input();
+                binding.execute(&mut self.ctx);
+                return true;
+            }
+        }
+
+        if continued {
+            self.chord.push(key, *mods);
+            return true;
+        }
+
+        if self.chord.is_pending() {
+            self.abandon_chord();
+        }
+
+        false
+    }
+
+    /// Abandon the pending chord once it timed out
+    pub fn check_chord_timeout(&mut self) {
+        if self.chord.is_pending() && self.chord.is_expired(self.chord_timeout) {
+            self.abandon_chord();
+        }
+    }
+
+    /// Process the input of an abandoned chord as if it had no bindings
+    fn abandon_chord(&mut self) {
+        debug!("abandoning chord {:?}", self.chord.describe());
+        for input in self.chord.take_input() {
+            match input {
+                ChordInput::Key(key, mods) => {
+                    *self.ctx.last_modifiers() = mods;
+                    *self.ctx.received_count() = 0;
+                    *self.ctx.suppress_chars() = self.process_vt52_key(&mods, key) ||
+                        self.process_key_bindings(&mods, key);
+                },
+                ChordInput::Char(c) => self.received_char(c),
+            }
+        }
+    }
+
+    /// Send the VT52 sequence of a cursor or PF key
+    ///
+    /// In VT52 mode these keys take precedence over the ANSI sequences of the
+    /// key bindings. Returns true if a sequence was sent.
+    fn process_vt52_key(&mut self, mods: &ModifiersState, key: KeyPress) -> bool {
+        let modified = mods.shift || mods.ctrl || mods.alt || mods.logo;
+        if modified || !self.ctx.terminal_mode().contains(TermMode::VT52) {
+            return false;
+        }
+
+        let app_keypad = self.ctx.terminal_mode().contains(TermMode::APP_KEYPAD);
+        match key.keycode.and_then(|keycode| vt52_key(keycode, app_keypad)) {
+            Some(bytes) => {
+                self.ctx.write_to_pty(bytes);
+                true
+            },
+            None => false,
+        }
+    }
+
     /// Attempts to find a binding and execute its action
     ///
     /// The provided mode, mods, and key must match what is allowed by a binding
     /// for its action to be executed.
     ///
     /// Returns true if an action is executed.
-    fn process_mouse_bindings(&mut self, mods: &ModifiersState, button: MouseButton) -> bool {
+    fn process_mouse_bindings(&mut self, mods: &ModifiersState, trigger: MouseTrigger) -> bool {
         for binding in self.mouse_bindings {
-            if binding.is_triggered_by(self.ctx.terminal_mode(), mods, &button) {
+            if binding.is_triggered_by(self.ctx.terminal_mode(), mods, &trigger) &&
+                binding.condition_matches(&self.ctx)
+            {
                 // binding was triggered; run the action
                 binding.execute(&mut self.ctx);
                 return true;
@@ -612,22 +1153,75 @@ impl<'a, A: ActionContext + 'a> Processor<'a, A> {
     }
 }
 
+/// Sequence sent by a VT52 for a cursor, PF or keypad key
+///
+/// Keypad keys only send sequences of their own in alternate keypad mode.
+fn vt52_key(key: VirtualKeyCode, app_keypad: bool) -> Option<&'static [u8]> {
+    if app_keypad {
+        if let Some(sequence) = vt52_keypad_key(key) {
+            return Some(sequence);
+        }
+    }
+
+    Some(match key {
+        VirtualKeyCode::Up => b"\x1bA",
+        VirtualKeyCode::Down => b"\x1bB",
+        VirtualKeyCode::Right => b"\x1bC",
+        VirtualKeyCode::Left => b"\x1bD",
+        VirtualKeyCode::F1 => b"\x1bP",
+        VirtualKeyCode::F2 => b"\x1bQ",
+        VirtualKeyCode::F3 => b"\x1bR",
+        VirtualKeyCode::F4 => b"\x1bS",
+        _ => return None,
+    })
+}
+
+/// Sequence sent by a VT52 keypad key in alternate keypad mode
+fn vt52_keypad_key(key: VirtualKeyCode) -> Option<&'static [u8]> {
+    Some(match key {
+        VirtualKeyCode::Numpad0 => b"\x1b?p",
+        VirtualKeyCode::Numpad1 => b"\x1b?q",
+        VirtualKeyCode::Numpad2 => b"\x1b?r",
+        VirtualKeyCode::Numpad3 => b"\x1b?s",
+        VirtualKeyCode::Numpad4 => b"\x1b?t",
+        VirtualKeyCode::Numpad5 => b"\x1b?u",
+        VirtualKeyCode::Numpad6 => b"\x1b?v",
+        VirtualKeyCode::Numpad7 => b"\x1b?w",
+        VirtualKeyCode::Numpad8 => b"\x1b?x",
+        VirtualKeyCode::Numpad9 => b"\x1b?y",
+        VirtualKeyCode::Subtract => b"\x1b?m",
+        VirtualKeyCode::NumpadComma => b"\x1b?l",
+        VirtualKeyCode::Decimal => b"\x1b?n",
+        VirtualKeyCode::NumpadEnter => b"\x1b?M",
+        _ => return None,
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use std::borrow::Cow;
+    use std::path::PathBuf;
     use std::time::Duration;
 
     use glutin::{VirtualKeyCode, Event, WindowEvent, ElementState, MouseButton, ModifiersState};
+    use glutin::{MouseScrollDelta, TouchPhase};
 
     use term::{SizeInfo, Term, TermMode};
     use event::{Mouse, ClickState};
-    use config::{self, Config, ClickHandler};
+    use config::{self, Config, ClickHandler, FileQuoting};
     use index::{Point, Side};
     use selection::Selection;
+    use recorder::Recorder;
+    use snippet::Snippet;
 
-    use super::{Action, Binding, Processor};
+    use super::{Action, AltKeys, Binding, Chord, Condition, KeyBinding, KeyPress, KeyTrigger};
+    use super::{MouseBinding, MouseTrigger, Processor};
 
-    const KEY: VirtualKeyCode = VirtualKeyCode::Key0;
+    const KEY: KeyTrigger = KeyTrigger::Keycode(VirtualKeyCode::Key0);
+
+    fn press(keycode: VirtualKeyCode) -> KeyPress {
+        KeyPress { keycode: Some(keycode), scancode: 0 }
+    }
 
     #[derive(PartialEq)]
     enum MultiClick {
@@ -645,11 +1239,19 @@ mod tests {
         pub received_count: usize,
         pub suppress_chars: bool,
         pub last_modifiers: ModifiersState,
+        pub alt_keys: AltKeys,
+        pub written: Vec<u8>,
+        pub foreground_process: Option<String>,
+        pub recorder: Recorder,
+        pub paste_config: config::PasteConfig,
+        pub pending_paste: Option<String>,
     }
 
     impl <'a>super::ActionContext for ActionContext<'a> {
-        fn write_to_pty<B: Into<Cow<'static, [u8]>>>(&mut self, _val: B) {
-            // STUBBED
+        fn write_to_pty<B: Into<Cow<'static, [u8]>>>(&mut self, val: B) {
+            let val = val.into();
+            self.recorder.record(&val);
+            self.written.extend_from_slice(&val);
         }
 
         fn terminal_mode(&self) -> TermMode {
@@ -694,12 +1296,112 @@ mod tests {
         fn last_modifiers(&mut self) -> &mut ModifiersState {
             &mut self.last_modifiers
         }
+        fn alt_keys(&mut self) -> &mut AltKeys {
+            &mut self.alt_keys
+        }
+        fn foreground_process(&self) -> Option<String> {
+            self.foreground_process.clone()
+        }
+        fn title(&self) -> Option<&str> {
+            self.terminal.title()
+        }
+        fn recorder(&mut self) -> &mut Recorder {
+            &mut self.recorder
+        }
+        fn selection_text(&self) -> Option<String> {
+            self.selection.as_ref()
+                .and_then(|selection| selection.to_span(self.terminal))
+                .map(|span| self.terminal.string_from_selection(&span))
+        }
+        fn working_directory(&self) -> Option<&str> {
+            self.terminal.working_directory()
+        }
+        fn paste_config(&self) -> &config::PasteConfig {
+            &self.paste_config
+        }
+        fn pending_paste(&mut self) -> &mut Option<String> {
+            &mut self.pending_paste
+        }
         fn change_font_size(&mut self, _delta: i8) {
         }
         fn reset_font_size(&mut self) {
         }
     }
 
+    /// State borrowed by the processor under test
+    struct TestState {
+        size: SizeInfo,
+        terminal: Term,
+        selection: Option<Selection>,
+        mouse: Mouse,
+        mouse_config: config::Mouse,
+        alt_config: config::AltConfig,
+        chord: Chord,
+    }
+
+    impl TestState {
+        fn new() -> TestState {
+            let size = SizeInfo {
+                width: 21.0,
+                height: 51.0,
+                cell_width: 3.0,
+                cell_height: 3.0,
+                padding_x: 0.0,
+                padding_y: 0.0,
+            };
+
+            TestState {
+                size,
+                terminal: Term::new(&Config::default(), size),
+                selection: None,
+                mouse: Mouse::default(),
+                mouse_config: config::Mouse {
+                    double_click: ClickHandler {
+                        threshold: Duration::from_millis(1000),
+                    },
+                    triple_click: ClickHandler {
+                        threshold: Duration::from_millis(1000),
+                    },
+                    faux_scrollback_lines: 1,
+                },
+                alt_config: Default::default(),
+                chord: Default::default(),
+            }
+        }
+
+        /// Create a processor with the given bindings and a fresh context
+        fn processor<'a>(
+            &'a mut self,
+            key_bindings: &'a [KeyBinding],
+            mouse_bindings: &'a [MouseBinding],
+        ) -> Processor<'a, ActionContext<'a>> {
+            Processor {
+                ctx: ActionContext {
+                    terminal: &mut self.terminal,
+                    selection: &mut self.selection,
+                    mouse: &mut self.mouse,
+                    size_info: &self.size,
+                    last_action: MultiClick::None,
+                    received_count: 0,
+                    suppress_chars: false,
+                    last_modifiers: ModifiersState::default(),
+                    alt_keys: AltKeys::default(),
+                    written: Vec::new(),
+                    foreground_process: None,
+                    recorder: Recorder::default(),
+                    paste_config: Default::default(),
+                    pending_paste: None,
+                },
+                alt_config: &self.alt_config,
+                chord: &mut self.chord,
+                chord_timeout: Duration::from_millis(1000),
+                mouse_config: &self.mouse_config,
+                key_bindings,
+                mouse_bindings,
+            }
+        }
+    }
+
     macro_rules! test_clickstate {
         {
             name: $name:ident,
@@ -736,10 +1438,19 @@ mod tests {
                     received_count: 0,
                     suppress_chars: false,
                     last_modifiers: ModifiersState::default(),
+                    alt_keys: AltKeys::default(),
+                    written: Vec::new(),
+                    foreground_process: None,
+                    recorder: Recorder::default(),
+                    paste_config: Default::default(),
+                    pending_paste: None,
                 };
 
                 let mut processor = Processor {
                     ctx: context,
+                    alt_config: &Default::default(),
+                    chord: &mut Default::default(),
+                    chord_timeout: Duration::from_millis(1000),
                     mouse_config: &config::Mouse {
                         double_click: ClickHandler {
                             threshold: Duration::from_millis(1000),
@@ -784,6 +1495,26 @@ mod tests {
         }
     }
 
+    macro_rules! test_process_mouse_binding {
+        {
+            name: $name:ident,
+            binding: $binding:expr,
+            trigger: $trigger:expr,
+            triggers: $triggers:expr,
+            mode: $mode:expr,
+            mods: $mods:expr
+        } => {
+            #[test]
+            fn $name() {
+                if $triggers {
+                    assert!($binding.is_triggered_by($mode, &$mods, &$trigger));
+                } else {
+                    assert!(!$binding.is_triggered_by($mode, &$mods, &$trigger));
+                }
+            }
+        }
+    }
+
     test_clickstate! {
         name: single_click,
         initial_state: ClickState::None,
@@ -832,9 +1563,312 @@ mod tests {
         last_action: MultiClick::TripleClick
     }
 
+    #[test]
+    fn vt52_cursor_keys() {
+        assert_eq!(super::vt52_key(VirtualKeyCode::Up, false), Some(&b"\x1bA"[..]));
+        assert_eq!(super::vt52_key(VirtualKeyCode::F4, false), Some(&b"\x1bS"[..]));
+        assert_eq!(super::vt52_key(VirtualKeyCode::Home, false), None);
+        assert_eq!(super::vt52_key(VirtualKeyCode::Numpad7, false), None);
+        assert_eq!(super::vt52_key(VirtualKeyCode::Numpad7, true), Some(&b"\x1b?w"[..]));
+        assert_eq!(super::vt52_key(VirtualKeyCode::NumpadEnter, true), Some(&b"\x1b?M"[..]));
+        assert_eq!(super::vt52_key(VirtualKeyCode::Up, true), Some(&b"\x1bA"[..]));
+    }
+
+    #[test]
+    fn wheel_bindings() {
+        let ctrl = ModifiersState { shift: false, ctrl: true, alt: false, logo: false };
+        let bindings = vec![Binding {
+            trigger: MouseTrigger::WheelUp,
+            mods: ctrl,
+            action: Action::from("zoom"),
+            mode: TermMode::NONE,
+            notmode: TermMode::NONE,
+            leader: Vec::new(),
+            condition: None,
+        }];
+
+        let mut state = TestState::new();
+        ::ansi::Handler::set_mode(&mut state.terminal, ::ansi::Mode::SwapScreenAndSetRestoreCursor);
+        let mut processor = state.processor(&[], &bindings[..]);
+
+        let delta = MouseScrollDelta::LineDelta(0.0, 2.0);
+        processor.on_mouse_wheel(delta, TouchPhase::Moved, ctrl);
+        assert_eq!(processor.ctx.written, b"zoomzoom");
+
+        // Without a matching binding the wheel scrolls as usual
+        processor.ctx.written.clear();
+        processor.on_mouse_wheel(delta, TouchPhase::Moved, ModifiersState::default());
+        assert_eq!(processor.ctx.written, b"\x1bOA\x1bOA");
+    }
+
+    /// Type `c` while Alt is held, returning what's sent to the pty
+    fn type_with_alt(alt_config: &str, alt_keys: AltKeys, c: char) -> Vec<u8> {
+        let mut state = TestState::new();
+        state.alt_config = ::serde_yaml::from_str::<config::AltConfig>(alt_config).unwrap();
+
+        let mut processor = state.processor(&[], &[]);
+        processor.ctx.last_modifiers = ModifiersState { shift: false, ctrl: false, alt: true, logo: false };
+        processor.ctx.alt_keys = alt_keys;
+
+        processor.received_char(c);
+        processor.ctx.written
+    }
+
+    #[test]
+    fn alt_sends_meta() {
+        let left = AltKeys { left: true, right: false };
+        let right = AltKeys { left: false, right: true };
+
+        assert_eq!(type_with_alt("{}", left, 'f'), b"\x1bf");
+        assert_eq!(type_with_alt("{ send: EightBit }", left, 'f'), "\u{e6}".as_bytes());
+        assert_eq!(type_with_alt("{ send: None }", left, 'f'), b"f");
+        assert_eq!(type_with_alt("{ right: false }", right, 'f'), b"f");
+        assert_eq!(type_with_alt("{ right: false }", AltKeys::default(), 'f'), b"\x1bf");
+        assert_eq!(type_with_alt("{ left: false }", AltKeys::default(), 'f'), b"f");
+        assert_eq!(type_with_alt("{}", left, 'ä'), "ä".as_bytes());
+    }
+
+    #[test]
+    fn chord_bindings() {
+        let ctrl = ModifiersState { shift: false, ctrl: true, alt: false, logo: false };
+        let none = ModifiersState::default();
+        let bindings = vec![Binding {
+            trigger: KeyTrigger::Keycode(VirtualKeyCode::C),
+            mods: none,
+            action: Action::from("chord"),
+            mode: TermMode::NONE,
+            notmode: TermMode::NONE,
+            leader: vec![(KeyTrigger::Keycode(VirtualKeyCode::A), ctrl)],
+            condition: None,
+        }];
+
+        let mut state = TestState::new();
+        let mut processor = state.processor(&bindings[..], &[]);
+
+        // Completed chord runs the action
+        processor.process_key(ElementState::Pressed, press(VirtualKeyCode::A), &ctrl);
+        processor.received_char('\x01');
+        assert_eq!(processor.chord.describe(), Some(String::from("Ctrl+A")));
+        processor.process_key(ElementState::Pressed, press(VirtualKeyCode::C), &none);
+        processor.received_char('c');
+        assert!(!processor.chord.is_pending());
+        assert_eq!(processor.ctx.written, b"chord");
+
+        // Abandoned chord forwards the original keys
+        processor.ctx.written.clear();
+        processor.process_key(ElementState::Pressed, press(VirtualKeyCode::A), &ctrl);
+        processor.received_char('\x01');
+        processor.process_key(ElementState::Pressed, press(VirtualKeyCode::X), &none);
+        processor.received_char('x');
+        assert!(!processor.chord.is_pending());
+        assert_eq!(processor.ctx.written, b"\x01x");
+    }
+
+    #[test]
+    fn record_and_replay_macro() {
+        let none = ModifiersState::default();
+        let binding = |key, action| Binding {
+            trigger: KeyTrigger::Keycode(key),
+            mods: none,
+            action,
+            mode: TermMode::NONE,
+            notmode: TermMode::NONE,
+            leader: Vec::new(),
+            condition: None,
+        };
+        let bindings = vec![
+            binding(VirtualKeyCode::F1, Action::RecordMacro(String::from("a"))),
+            binding(VirtualKeyCode::F2, Action::ReplayMacro(String::from("a"), 3)),
+        ];
+
+        let mut state = TestState::new();
+        let mut processor = state.processor(&bindings[..], &[]);
+
+        processor.process_key(ElementState::Pressed, press(VirtualKeyCode::F1), &none);
+        for &(key, c) in &[(VirtualKeyCode::L, 'l'), (VirtualKeyCode::S, 's')] {
+            processor.process_key(ElementState::Pressed, press(key), &none);
+            processor.received_char(c);
+        }
+        processor.process_key(ElementState::Pressed, press(VirtualKeyCode::F1), &none);
+        assert_eq!(processor.ctx.recorder.recording(), None);
+
+        processor.process_key(ElementState::Pressed, press(VirtualKeyCode::F2), &none);
+        assert_eq!(processor.ctx.written, b"lslslsls");
+    }
+
+    #[test]
+    fn snippet_binding() {
+        let mut state = TestState::new();
+        ::ansi::Handler::set_working_directory(&mut state.terminal, "/srv/logs");
+        let mut processor = state.processor(&[], &[]);
+
+        let snippet = Snippet::new("cd {cwd}\n", true).unwrap();
+        Action::Snippet(snippet.clone()).execute(&mut processor.ctx);
+        assert_eq!(processor.ctx.written, b"cd /srv/logs\r");
+
+        // Bracketed if the application asked for it
+        processor.ctx.written.clear();
+        ::ansi::Handler::set_mode(processor.ctx.terminal, ::ansi::Mode::BracketedPaste);
+        Action::Snippet(snippet).execute(&mut processor.ctx);
+        assert_eq!(processor.ctx.written, b"\x1b[200~cd /srv/logs\n\x1b[201~");
+    }
+
+    #[test]
+    fn sanitize_paste() {
+        // The end of the bracketing can't be smuggled in by splitting it
+        let pasted = String::from("ls\x1b[20\x1b[201~1~; rm -rf ~\u{9b}201~");
+        assert_eq!(super::sanitize_paste(pasted.clone(), false), "ls; rm -rf ~");
+        assert_eq!(super::sanitize_paste(pasted, true), "ls[20[201~1~; rm -rf ~201~");
+
+        let pasted = String::from("a\tb\r\nc\x07\x08d");
+        assert_eq!(super::sanitize_paste(pasted.clone(), false), pasted);
+        assert_eq!(super::sanitize_paste(pasted, true), "a\tb\r\ncd");
+    }
+
+    #[test]
+    fn confirm_risky_paste() {
+        let mut state = TestState::new();
+        let mut processor = state.processor(&[], &[]);
+        let none = ModifiersState::default();
+
+        // Nothing is confirmed unless it's enabled
+        super::request_paste(&mut processor.ctx, String::from("make\nmake install\n"));
+        assert_eq!(processor.ctx.written, b"make\rmake install\r");
+
+        processor.ctx.written.clear();
+        processor.ctx.paste_config = ::serde_yaml::from_str("{ confirm_multiline: true }").unwrap();
+
+        // A single line is pasted right away
+        super::request_paste(&mut processor.ctx, String::from("make"));
+        assert_eq!(processor.ctx.written, b"make");
+
+        // Multiple lines wait for confirmation, swallowing other keys
+        processor.ctx.written.clear();
+        super::request_paste(&mut processor.ctx, String::from("make\nmake install\n"));
+        assert_eq!(processor.ctx.pending_paste.as_ref().map(String::as_str), Some("make\nmake install\n"));
+        assert_eq!(super::paste_prompt("make\nmake install\n"), "Paste 2 lines (18 bytes)? Enter to paste, Esc to cancel");
+
+        processor.process_key(ElementState::Pressed, press(VirtualKeyCode::A), &none);
+        processor.received_char('a');
+        assert!(processor.ctx.pending_paste.is_some());
+        assert_eq!(processor.ctx.written, b"");
+
+        processor.process_key(ElementState::Pressed, press(VirtualKeyCode::Return), &none);
+        processor.received_char('\r');
+        assert_eq!(processor.ctx.pending_paste, None);
+        assert_eq!(processor.ctx.written, b"make\rmake install\r");
+
+        // Cancelled pastes are dropped
+        processor.ctx.written.clear();
+        super::request_paste(&mut processor.ctx, String::from("rm -rf ~\n"));
+        processor.process_key(ElementState::Pressed, press(VirtualKeyCode::Escape), &none);
+        assert_eq!(processor.ctx.pending_paste, None);
+        assert_eq!(processor.ctx.written, b"");
+
+        // Line breaks are harmless in bracketed mode
+        ::ansi::Handler::set_mode(processor.ctx.terminal, ::ansi::Mode::BracketedPaste);
+        super::request_paste(&mut processor.ctx, String::from("a\nb"));
+        assert_eq!(processor.ctx.written, b"\x1b[200~a\nb\x1b[201~");
+    }
+
+    #[test]
+    fn quote_path() {
+        let quote = |path, quoting| super::quote_path(path, quoting).unwrap();
+        let path = "/tmp/My Files/it's $HOME!.txt";
+        assert_eq!(quote(path, FileQuoting::Single), "'/tmp/My Files/it'\\''s $HOME!.txt'");
+        assert_eq!(quote(path, FileQuoting::Double), "\"/tmp/My Files/it's \\$HOME\"\\!\".txt\"");
+        assert_eq!(quote(path, FileQuoting::Backslash), "/tmp/My\\ Files/it\\'s\\ \\$HOME\\!.txt");
+        assert_eq!(quote("/tmp/naïve.txt", FileQuoting::Backslash), "/tmp/naïve.txt");
+
+        for &quoting in &[FileQuoting::Single, FileQuoting::Double, FileQuoting::Backslash] {
+            assert_eq!(super::quote_path("/tmp/a\nrm -rf ~", quoting), None);
+        }
+    }
+
+    #[test]
+    fn drop_files() {
+        let mut state = TestState::new();
+        let mut processor = state.processor(&[], &[]);
+
+        let paths = [PathBuf::from("/tmp/a b.txt"), PathBuf::from("/tmp/c.txt")];
+        processor.drop_files(&paths);
+        assert_eq!(processor.ctx.written, b"'/tmp/a b.txt' '/tmp/c.txt'");
+
+        processor.ctx.written.clear();
+        ::ansi::Handler::set_mode(processor.ctx.terminal, ::ansi::Mode::BracketedPaste);
+        processor.drop_files(&paths[1..]);
+        assert_eq!(processor.ctx.written, b"\x1b[200~'/tmp/c.txt'\x1b[201~");
+
+        // Paths with control characters are left out
+        processor.ctx.written.clear();
+        processor.drop_files(&[PathBuf::from("/tmp/a\nb")]);
+        assert_eq!(processor.ctx.written, b"");
+    }
+
+    #[test]
+    fn scancode_bindings() {
+        let none = ModifiersState::default();
+        let binding = |trigger| Binding {
+            trigger,
+            mods: none,
+            action: Action::Copy,
+            mode: TermMode::NONE,
+            notmode: TermMode::NONE,
+            leader: Vec::new(),
+            condition: None,
+        };
+
+        // A Cyrillic layout has no keycode for the key labeled A on QWERTY
+        let cyrillic = KeyPress { keycode: None, scancode: 38 };
+        let dvorak = KeyPress { keycode: Some(VirtualKeyCode::A), scancode: 30 };
+
+        let scancode = binding(KeyTrigger::Scancode(38));
+        assert!(scancode.is_triggered_by(TermMode::NONE, &none, &cyrillic));
+        assert!(!scancode.is_triggered_by(TermMode::NONE, &none, &dvorak));
+
+        let keycode = binding(KeyTrigger::Keycode(VirtualKeyCode::A));
+        assert!(!keycode.is_triggered_by(TermMode::NONE, &none, &cyrillic));
+        assert!(keycode.is_triggered_by(TermMode::NONE, &none, &dvorak));
+    }
+
+    #[test]
+    fn conditional_bindings() {
+        let ctrl_shift = ModifiersState { shift: true, ctrl: true, alt: false, logo: false };
+        let binding = |action: &'static str, condition| Binding {
+            trigger: KeyTrigger::Keycode(VirtualKeyCode::C),
+            mods: ctrl_shift,
+            action: Action::from(action),
+            mode: TermMode::NONE,
+            notmode: TermMode::NONE,
+            leader: Vec::new(),
+            condition: Some(condition),
+        };
+        let bindings = vec![
+            binding("title", Condition::Title { text: String::from("htop"), negated: false }),
+            binding("copy", Condition::Process { name: String::from("vim"), negated: true }),
+        ];
+
+        let mut state = TestState::new();
+        let mut processor = state.processor(&bindings[..], &[]);
+        processor.ctx.foreground_process = Some(String::from("bash"));
+
+        processor.process_key(ElementState::Pressed, press(VirtualKeyCode::C), &ctrl_shift);
+        assert_eq!(processor.ctx.written, b"copy");
+
+        // Inside vim the key isn't bound
+        processor.ctx.written.clear();
+        processor.ctx.foreground_process = Some(String::from("vim"));
+        processor.process_key(ElementState::Pressed, press(VirtualKeyCode::C), &ctrl_shift);
+        assert!(processor.ctx.written.is_empty());
+
+        ::ansi::Handler::set_title(processor.ctx.terminal, "htop - user@host");
+        processor.process_key(ElementState::Pressed, press(VirtualKeyCode::C), &ctrl_shift);
+        assert_eq!(processor.ctx.written, b"title");
+    }
+
     test_process_binding! {
         name: process_binding_nomode_shiftmod_require_shift,
-        binding: Binding { trigger: KEY, mods: ModifiersState { shift: true, ctrl: false, alt: false, logo: false }, action: Action::from("\x1b[1;2D"), mode: TermMode::NONE, notmode: TermMode::NONE },
+        binding: Binding { trigger: KEY, mods: ModifiersState { shift: true, ctrl: false, alt: false, logo: false }, action: Action::from("\x1b[1;2D"), mode: TermMode::NONE, notmode: TermMode::NONE, leader: Vec::new(), condition: None },
         triggers: true,
         mode: TermMode::NONE,
         mods: ModifiersState { shift: true, ctrl: false, alt: false, logo: false }
@@ -842,7 +1876,7 @@ mod tests {
 
     test_process_binding! {
         name: process_binding_nomode_nomod_require_shift,
-        binding: Binding { trigger: KEY, mods: ModifiersState { shift: true, ctrl: false, alt: false, logo: false }, action: Action::from("\x1b[1;2D"), mode: TermMode::NONE, notmode: TermMode::NONE },
+        binding: Binding { trigger: KEY, mods: ModifiersState { shift: true, ctrl: false, alt: false, logo: false }, action: Action::from("\x1b[1;2D"), mode: TermMode::NONE, notmode: TermMode::NONE, leader: Vec::new(), condition: None },
         triggers: false,
         mode: TermMode::NONE,
         mods: ModifiersState { shift: false, ctrl: false, alt: false, logo: false }
@@ -850,7 +1884,7 @@ mod tests {
 
     test_process_binding! {
         name: process_binding_nomode_controlmod,
-        binding: Binding { trigger: KEY, mods: ModifiersState { ctrl: true, shift: false, alt: false, logo: false }, action: Action::from("\x1b[1;5D"), mode: TermMode::NONE, notmode: TermMode::NONE },
+        binding: Binding { trigger: KEY, mods: ModifiersState { ctrl: true, shift: false, alt: false, logo: false }, action: Action::from("\x1b[1;5D"), mode: TermMode::NONE, notmode: TermMode::NONE, leader: Vec::new(), condition: None },
         triggers: true,
         mode: TermMode::NONE,
         mods: ModifiersState { ctrl: true, shift: false, alt: false, logo: false }
@@ -858,7 +1892,7 @@ mod tests {
 
     test_process_binding! {
         name: process_binding_nomode_nomod_require_not_appcursor,
-        binding: Binding { trigger: KEY, mods: ModifiersState { shift: false, ctrl: false, alt: false, logo: false }, action: Action::from("\x1b[D"), mode: TermMode::NONE, notmode: TermMode::APP_CURSOR },
+        binding: Binding { trigger: KEY, mods: ModifiersState { shift: false, ctrl: false, alt: false, logo: false }, action: Action::from("\x1b[D"), mode: TermMode::NONE, notmode: TermMode::APP_CURSOR, leader: Vec::new(), condition: None },
         triggers: true,
         mode: TermMode::NONE,
         mods: ModifiersState { shift: false, ctrl: false, alt: false, logo: false }
@@ -866,7 +1900,7 @@ mod tests {
 
     test_process_binding! {
         name: process_binding_appcursormode_nomod_require_appcursor,
-        binding: Binding { trigger: KEY, mods: ModifiersState { shift: false, ctrl: false, alt: false, logo: false }, action: Action::from("\x1bOD"), mode: TermMode::APP_CURSOR, notmode: TermMode::NONE },
+        binding: Binding { trigger: KEY, mods: ModifiersState { shift: false, ctrl: false, alt: false, logo: false }, action: Action::from("\x1bOD"), mode: TermMode::APP_CURSOR, notmode: TermMode::NONE, leader: Vec::new(), condition: None },
         triggers: true,
         mode: TermMode::APP_CURSOR,
         mods: ModifiersState { shift: false, ctrl: false, alt: false, logo: false }
@@ -874,7 +1908,7 @@ mod tests {
 
     test_process_binding! {
         name: process_binding_nomode_nomod_require_appcursor,
-        binding: Binding { trigger: KEY, mods: ModifiersState { shift: false, ctrl: false, alt: false, logo: false }, action: Action::from("\x1bOD"), mode: TermMode::APP_CURSOR, notmode: TermMode::NONE },
+        binding: Binding { trigger: KEY, mods: ModifiersState { shift: false, ctrl: false, alt: false, logo: false }, action: Action::from("\x1bOD"), mode: TermMode::APP_CURSOR, notmode: TermMode::NONE, leader: Vec::new(), condition: None },
         triggers: false,
         mode: TermMode::NONE,
         mods: ModifiersState { shift: false, ctrl: false, alt: false, logo: false }
@@ -882,7 +1916,7 @@ mod tests {
 
     test_process_binding! {
         name: process_binding_appcursormode_appkeypadmode_nomod_require_appcursor,
-        binding: Binding { trigger: KEY, mods: ModifiersState { shift: false, ctrl: false, alt: false, logo: false }, action: Action::from("\x1bOD"), mode: TermMode::APP_CURSOR, notmode: TermMode::NONE },
+        binding: Binding { trigger: KEY, mods: ModifiersState { shift: false, ctrl: false, alt: false, logo: false }, action: Action::from("\x1bOD"), mode: TermMode::APP_CURSOR, notmode: TermMode::NONE, leader: Vec::new(), condition: None },
         triggers: true,
         mode: TermMode::APP_CURSOR | TermMode::APP_KEYPAD,
         mods: ModifiersState { shift: false, ctrl: false, alt: false, logo: false }
@@ -890,9 +1924,45 @@ mod tests {
 
     test_process_binding! {
         name: process_binding_fail_with_extra_mods,
-        binding: Binding { trigger: KEY, mods: ModifiersState { shift: false, ctrl: false, alt: false, logo: true }, action: Action::from("arst"), mode: TermMode::NONE, notmode: TermMode::NONE },
+        binding: Binding { trigger: KEY, mods: ModifiersState { shift: false, ctrl: false, alt: false, logo: true }, action: Action::from("arst"), mode: TermMode::NONE, notmode: TermMode::NONE, leader: Vec::new(), condition: None },
         triggers: false,
         mode: TermMode::NONE,
         mods: ModifiersState { shift: false, ctrl: false, alt: true, logo: true }
     }
+
+    test_process_mouse_binding! {
+        name: process_mouse_binding_wheel_require_control,
+        binding: Binding { trigger: MouseTrigger::WheelUp, mods: ModifiersState { shift: false, ctrl: true, alt: false, logo: false }, action: Action::IncreaseFontSize, mode: TermMode::NONE, notmode: TermMode::NONE, leader: Vec::new(), condition: None },
+        trigger: MouseTrigger::WheelUp,
+        triggers: true,
+        mode: TermMode::NONE,
+        mods: ModifiersState { shift: false, ctrl: true, alt: false, logo: false }
+    }
+
+    test_process_mouse_binding! {
+        name: process_mouse_binding_wheel_nomod_require_control,
+        binding: Binding { trigger: MouseTrigger::WheelUp, mods: ModifiersState { shift: false, ctrl: true, alt: false, logo: false }, action: Action::IncreaseFontSize, mode: TermMode::NONE, notmode: TermMode::NONE, leader: Vec::new(), condition: None },
+        trigger: MouseTrigger::WheelUp,
+        triggers: false,
+        mode: TermMode::NONE,
+        mods: ModifiersState { shift: false, ctrl: false, alt: false, logo: false }
+    }
+
+    test_process_mouse_binding! {
+        name: process_mouse_binding_other_button,
+        binding: Binding { trigger: MouseTrigger::Button(MouseButton::Other(8)), mods: ModifiersState { shift: false, ctrl: false, alt: false, logo: false }, action: Action::Paste, mode: TermMode::NONE, notmode: TermMode::NONE, leader: Vec::new(), condition: None },
+        trigger: MouseTrigger::Button(MouseButton::Other(8)),
+        triggers: true,
+        mode: TermMode::NONE,
+        mods: ModifiersState { shift: false, ctrl: false, alt: false, logo: false }
+    }
+
+    test_process_mouse_binding! {
+        name: process_mouse_binding_double_click_ignores_single_click,
+        binding: Binding { trigger: MouseTrigger::DoubleClick(MouseButton::Left), mods: ModifiersState { shift: false, ctrl: false, alt: false, logo: false }, action: Action::Copy, mode: TermMode::NONE, notmode: TermMode::NONE, leader: Vec::new(), condition: None },
+        trigger: MouseTrigger::Button(MouseButton::Left),
+        triggers: false,
+        mode: TermMode::NONE,
+        mods: ModifiersState { shift: false, ctrl: false, alt: false, logo: false }
+    }
 }
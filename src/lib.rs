@@ -37,6 +37,7 @@ extern crate objc;
 extern crate arraydeque;
 extern crate cgmath;
 extern crate copypasta;
+extern crate encoding_rs;
 extern crate errno;
 extern crate env_logger;
 extern crate fnv;
@@ -50,6 +51,7 @@ extern crate parking_lot;
 extern crate serde;
 extern crate serde_json;
 extern crate serde_yaml;
+extern crate unicode_bidi;
 extern crate unicode_width;
 extern crate vte;
 extern crate xdg;
@@ -62,6 +64,7 @@ pub mod ansi;
 pub mod cli;
 pub mod config;
 pub mod display;
+pub mod encoding;
 pub mod event;
 pub mod event_loop;
 pub mod grid;
@@ -70,8 +73,10 @@ pub mod input;
 pub mod locale;
 pub mod logging;
 pub mod meter;
+pub mod recorder;
 pub mod renderer;
 pub mod selection;
+pub mod snippet;
 pub mod sync;
 pub mod term;
 pub mod tty;
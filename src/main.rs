@@ -114,7 +114,9 @@ fn run(mut config: Config, options: &cli::Options) -> Result<(), Box<Error>> {
     // This object contains all of the state about what's being displayed. It's
     // wrapped in a clonable mutex since both the I/O loop and display need to
     // access it.
-    let terminal = Term::new(&config, display.size().to_owned());
+    let mut encoding = options.encoding.unwrap_or_else(|| config.encoding());
+    let mut terminal = Term::new(&config, display.size().to_owned());
+    terminal.set_encoding(encoding);
     let terminal = Arc::new(FairMutex::new(terminal));
 
     // Find the window ID for setting $WINDOWID
@@ -137,6 +139,7 @@ fn run(mut config: Config, options: &cli::Options) -> Result<(), Box<Error>> {
         Arc::clone(&terminal),
         display.notifier(),
         pty.reader(),
+        encoding,
         options.ref_test,
     );
 
@@ -154,6 +157,7 @@ fn run(mut config: Config, options: &cli::Options) -> Result<(), Box<Error>> {
         &config,
         options.ref_test,
         display.size().to_owned(),
+        pty.foreground_process(),
     );
 
     // Create a config monitor when config was loaded from path
@@ -173,6 +177,12 @@ fn run(mut config: Config, options: &cli::Options) -> Result<(), Box<Error>> {
     // Kick off the I/O thread
     let io_thread = event_loop.spawn(None);
 
+    // Replay the macro requested on the command line; the pty buffers it until
+    // the shell reads its input
+    if let Some((ref register, count)) = options.replay {
+        processor.replay_macro(register, count);
+    }
+
     // Main display loop
     loop {
         // Process input and window events
@@ -187,6 +197,15 @@ fn run(mut config: Config, options: &cli::Options) -> Result<(), Box<Error>> {
                 processor.update_config(&config);
                 terminal.update_config(&config);
                 terminal.dirty = true;
+
+                // An encoding given on the command line takes precedence
+                let new_encoding = options.encoding.unwrap_or_else(|| config.encoding());
+                if new_encoding != encoding {
+                    encoding = new_encoding;
+                    terminal.set_encoding(encoding);
+                    loop_tx.send(Msg::Encoding(encoding))
+                        .expect("Error sending encoding to event loop");
+                }
             });
 
         // Maybe draw the terminal
@@ -200,7 +219,13 @@ fn run(mut config: Config, options: &cli::Options) -> Result<(), Box<Error>> {
             display.handle_resize(&mut terminal, &config, &mut [&mut pty, &mut processor]);
 
             // Draw the current state of the terminal
-            display.draw(terminal, &config, processor.selection.as_ref());
+            display.draw(
+                terminal,
+                &config,
+                processor.selection.as_ref(),
+                processor.pending_chord(),
+                processor.paste_prompt(),
+            );
         }
 
         // Begin shutdown if the flag was raised.
@@ -0,0 +1,235 @@
+// Copyright 2016 Joe Wilm, The Alacritty Project Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+//! Keyboard macros
+//!
+//! Input written to the pty can be recorded into named registers and replayed
+//! later on. Registers are stored in `registers.yml` in the config directory,
+//! so they are shared between windows and survive restarts.
+use std::collections::BTreeMap;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+
+use serde_yaml;
+
+use util::fmt::Red;
+
+/// Records input into registers and looks up recorded input
+#[derive(Debug, Default)]
+pub struct Recorder {
+    /// File the registers are stored in; without it they are kept in memory
+    path: Option<PathBuf>,
+
+    /// Registers as of the last time they were read or written
+    registers: BTreeMap<String, Register>,
+
+    /// Register being recorded, and the input recorded so far
+    recording: Option<(String, Vec<u8>)>,
+}
+
+/// Input stored in a register
+///
+/// Registers are written as strings, unless the input isn't valid UTF-8, like
+/// legacy mouse reports on large terminals or 8-bit meta. Those are written as
+/// a list of bytes so nothing is lost.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+enum Register {
+    Text(String),
+    Bytes(Vec<u8>),
+}
+
+impl Register {
+    fn new(input: Vec<u8>) -> Register {
+        match String::from_utf8(input) {
+            Ok(text) => Register::Text(text),
+            Err(err) => Register::Bytes(err.into_bytes()),
+        }
+    }
+
+    fn as_bytes(&self) -> &[u8] {
+        match *self {
+            Register::Text(ref text) => text.as_bytes(),
+            Register::Bytes(ref bytes) => bytes,
+        }
+    }
+}
+
+impl Recorder {
+    /// Create a recorder storing its registers in `path`
+    pub fn new(path: Option<PathBuf>) -> Recorder {
+        let mut recorder = Recorder {
+            path,
+            ..Recorder::default()
+        };
+        recorder.reload();
+        recorder
+    }
+
+    /// Location of the registers file in the config directory
+    pub fn default_path() -> Option<PathBuf> {
+        ::xdg::BaseDirectories::with_prefix("alacritty")
+            .ok()
+            .and_then(|xdg| xdg.place_config_file("registers.yml").ok())
+    }
+
+    /// Name of the register being recorded
+    #[inline]
+    pub fn recording(&self) -> Option<&str> {
+        self.recording.as_ref().map(|recording| recording.0.as_str())
+    }
+
+    /// Start recording into `register`
+    ///
+    /// The register is only overwritten once the recording is stopped.
+    pub fn start(&mut self, register: &str) {
+        info!("recording register {}", register);
+        self.recording = Some((register.to_owned(), Vec::new()));
+    }
+
+    /// Stop recording and store what was recorded
+    pub fn stop(&mut self) {
+        let (register, input) = match self.recording.take() {
+            Some(recording) => recording,
+            None => return,
+        };
+
+        info!("recorded register {}", register);
+
+        // Don't discard registers recorded in other windows meanwhile
+        self.reload();
+        self.registers.insert(register, Register::new(input));
+        self.save();
+    }
+
+    /// Record input written to the pty
+    #[inline]
+    pub fn record(&mut self, input: &[u8]) {
+        if let Some((_, ref mut recorded)) = self.recording {
+            recorded.extend_from_slice(input);
+        }
+    }
+
+    /// Input stored in `register`, repeated `count` times
+    pub fn replay(&mut self, register: &str, count: usize) -> Option<Vec<u8>> {
+        self.reload();
+
+        self.registers.get(register).map(|input| {
+            let input = input.as_bytes();
+            let mut replay = Vec::with_capacity(input.len() * count);
+            for _ in 0..count {
+                replay.extend_from_slice(input);
+            }
+            replay
+        })
+    }
+
+    fn reload(&mut self) {
+        if let Some(ref path) = self.path {
+            match read_registers(path) {
+                Ok(registers) => self.registers = registers,
+                Err(ref err) if err.kind() == io::ErrorKind::NotFound => (),
+                Err(err) => warn!("Unable to read registers from {:?}: {}", path, Red(err)),
+            }
+        }
+    }
+
+    fn save(&self) {
+        if let Some(ref path) = self.path {
+            if let Err(err) = write_registers(path, &self.registers) {
+                warn!("Unable to write registers to {:?}: {}", path, Red(err));
+            }
+        }
+    }
+}
+
+fn read_registers(path: &Path) -> io::Result<BTreeMap<String, Register>> {
+    let mut contents = String::new();
+    File::open(path)?.read_to_string(&mut contents)?;
+
+    if contents.trim().is_empty() {
+        return Ok(BTreeMap::new());
+    }
+
+    serde_yaml::from_str(&contents)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err.to_string()))
+}
+
+fn write_registers(path: &Path, registers: &BTreeMap<String, Register>) -> io::Result<()> {
+    let contents = serde_yaml::to_string(registers)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err.to_string()))?;
+
+    // Registers may contain passwords, so only the user may read them
+    OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(path)?
+        .write_all(contents.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+    use std::os::unix::fs::PermissionsExt;
+
+    use super::Recorder;
+
+    #[test]
+    fn record_and_replay() {
+        let mut recorder = Recorder::default();
+
+        // Input is only recorded while recording
+        recorder.record(b"ignored");
+        recorder.start("a");
+        assert_eq!(recorder.recording(), Some("a"));
+        recorder.record(b"show ");
+        recorder.record(b"version\r");
+        recorder.stop();
+        recorder.record(b"ignored");
+
+        assert_eq!(recorder.recording(), None);
+        assert_eq!(recorder.replay("a", 2), Some(b"show version\rshow version\r".to_vec()));
+        assert_eq!(recorder.replay("b", 1), None);
+    }
+
+    #[test]
+    fn registers_are_persisted() {
+        let path = env::temp_dir().join("alacritty_registers_are_persisted.yml");
+        let _ = fs::remove_file(&path);
+
+        let mut recorder = Recorder::new(Some(path.clone()));
+        recorder.start("login");
+        recorder.record(b"admin\r\x1b[A");
+        recorder.stop();
+
+        // Invalid UTF-8, like a legacy mouse report, is kept as it is
+        recorder.start("click");
+        recorder.record(b"\x1b[M \xff\xff\xe9");
+        recorder.stop();
+
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+
+        let mut other = Recorder::new(Some(path.clone()));
+        assert_eq!(other.replay("login", 1), Some(b"admin\r\x1b[A".to_vec()));
+        assert_eq!(other.replay("click", 1), Some(b"\x1b[M \xff\xff\xe9".to_vec()));
+
+        fs::remove_file(&path).unwrap();
+    }
+}
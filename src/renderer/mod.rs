@@ -780,10 +780,10 @@ impl<'a> RenderApi<'a> {
     pub fn render_string(
         &mut self,
         string: &str,
+        line: Line,
         glyph_cache: &mut GlyphCache,
         color: Rgb,
     ) {
-        let line = Line(23);
         let col = Column(0);
 
         let cells = string.chars()
@@ -792,6 +792,7 @@ impl<'a> RenderApi<'a> {
                 line,
                 column: col + i,
                 c,
+                zerowidth: &[],
                 bg: color,
                 fg: Rgb { r: 0, g: 0, b: 0 },
                 flags: cell::Flags::empty(),
@@ -817,12 +818,12 @@ impl<'a> RenderApi<'a> {
         }
     }
 
-    pub fn render_cells<I>(
+    pub fn render_cells<'b, I>(
         &mut self,
         cells: I,
         glyph_cache: &mut GlyphCache
     )
-        where I: Iterator<Item=RenderableCell>
+        where I: Iterator<Item=RenderableCell<'b>>
     {
         for cell in cells {
             // Get font key for cell
@@ -846,6 +847,18 @@ impl<'a> RenderApi<'a> {
                 self.add_render_item(&cell, glyph);
             }
 
+            // Draw the rest of the grapheme cluster on top of the base character
+            for c in cell.zerowidth_glyphs() {
+                let glyph_key = GlyphKey {
+                    font_key,
+                    size: glyph_cache.font_size,
+                    c: *c
+                };
+
+                let glyph = glyph_cache.get(&glyph_key, self);
+                self.add_render_item(&cell, glyph);
+            }
+
             // FIXME This is a super hacky way to do underlined text. During
             //       a time crunch to release 0.1, this seemed like a really
             //       easy, clean hack.
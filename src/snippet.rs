@@ -0,0 +1,212 @@
+// Copyright 2016 Joe Wilm, The Alacritty Project Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+//! Text snippets with placeholders
+//!
+//! A snippet template like `cd {cwd} && git log --since={date}` is parsed when
+//! the config is loaded, and its placeholders are filled in every time the
+//! snippet is written to the pty. Literal braces are written as `{{` and `}}`.
+use std::ffi::CString;
+use std::fmt;
+use std::mem;
+use std::ptr;
+
+use libc;
+
+/// Format of `{date}` without an explicit format
+const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// Value filled into a snippet when it's written
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Placeholder {
+    /// Text of the current selection
+    Selection,
+
+    /// Contents of the clipboard
+    Clipboard,
+
+    /// Working directory of the shell, as reported by OSC 7
+    WorkingDirectory,
+
+    /// Current local time, formatted with `strftime`
+    Date(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Part {
+    Text(String),
+    Placeholder(Placeholder),
+}
+
+/// Parsed snippet template
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snippet {
+    parts: Vec<Part>,
+
+    /// Write the snippet like pasted text, bracketed if the application asks
+    /// for it
+    paste: bool,
+}
+
+/// Errors occurring while parsing a snippet template
+#[derive(Debug, PartialEq, Eq)]
+pub enum Error {
+    /// Placeholder name which isn't known
+    UnknownPlaceholder(String),
+
+    /// `{` without a matching `}`
+    Unclosed,
+
+    /// `}` without a preceding `{`
+    Unopened,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::UnknownPlaceholder(ref name) => {
+                write!(f, "unknown snippet placeholder `{{{}}}`", name)
+            },
+            Error::Unclosed => f.write_str("unclosed `{` in snippet; write `{{` for a brace"),
+            Error::Unopened => f.write_str("unopened `}` in snippet; write `}}` for a brace"),
+        }
+    }
+}
+
+impl Snippet {
+    /// Parse a snippet template
+    pub fn new(template: &str, paste: bool) -> Result<Snippet, Error> {
+        let mut parts = Vec::new();
+        let mut text = String::new();
+        let mut chars = template.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    text.push('{');
+                },
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    text.push('}');
+                },
+                '{' => {
+                    let mut name = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => name.push(c),
+                            None => return Err(Error::Unclosed),
+                        }
+                    }
+
+                    if !text.is_empty() {
+                        parts.push(Part::Text(mem::replace(&mut text, String::new())));
+                    }
+                    parts.push(Part::Placeholder(parse_placeholder(name)?));
+                },
+                '}' => return Err(Error::Unopened),
+                c => text.push(c),
+            }
+        }
+
+        if !text.is_empty() {
+            parts.push(Part::Text(text));
+        }
+
+        Ok(Snippet { parts, paste })
+    }
+
+    #[inline]
+    pub fn paste(&self) -> bool {
+        self.paste
+    }
+
+    /// Fill in the placeholders with the values returned by `value`
+    pub fn expand<F>(&self, mut value: F) -> String
+        where F: FnMut(&Placeholder) -> String
+    {
+        let mut expanded = String::new();
+        for part in &self.parts {
+            match *part {
+                Part::Text(ref text) => expanded.push_str(text),
+                Part::Placeholder(ref placeholder) => expanded.push_str(&value(placeholder)),
+            }
+        }
+        expanded
+    }
+}
+
+fn parse_placeholder(name: String) -> Result<Placeholder, Error> {
+    Ok(match name.as_str() {
+        "selection" => Placeholder::Selection,
+        "clipboard" => Placeholder::Clipboard,
+        "cwd" => Placeholder::WorkingDirectory,
+        "date" => Placeholder::Date(DEFAULT_DATE_FORMAT.to_owned()),
+        _ if name.starts_with("date:") => Placeholder::Date(name[5..].to_owned()),
+        _ => return Err(Error::UnknownPlaceholder(name)),
+    })
+}
+
+/// Format the current local time with `strftime`
+pub fn format_date(format: &str) -> String {
+    let format = match CString::new(format) {
+        Ok(format) => format,
+        Err(_) => return String::new(),
+    };
+
+    let mut buf = [0u8; 256];
+    let len = unsafe {
+        let now = libc::time(ptr::null_mut());
+        let mut tm: libc::tm = mem::zeroed();
+        if libc::localtime_r(&now, &mut tm).is_null() {
+            return String::new();
+        }
+
+        libc::strftime(buf.as_mut_ptr() as *mut libc::c_char, buf.len(), format.as_ptr(), &tm)
+    };
+
+    String::from_utf8_lossy(&buf[..len]).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Error, Placeholder, Snippet};
+
+    #[test]
+    fn expand_placeholders() {
+        let snippet = Snippet::new("cd {cwd} && echo {{{selection}}} > {date:%Y}.log", false).unwrap();
+        let expanded = snippet.expand(|placeholder| match *placeholder {
+            Placeholder::Selection => String::from("sel"),
+            Placeholder::WorkingDirectory => String::from("/tmp"),
+            Placeholder::Date(ref format) => format.clone(),
+            Placeholder::Clipboard => unreachable!(),
+        });
+
+        assert_eq!(expanded, "cd /tmp && echo {sel} > %Y.log");
+    }
+
+    #[test]
+    fn invalid_templates() {
+        assert_eq!(Snippet::new("{home}", false), Err(Error::UnknownPlaceholder(String::from("home"))));
+        assert_eq!(Snippet::new("echo {cwd", false), Err(Error::Unclosed));
+        assert_eq!(Snippet::new("echo }", false), Err(Error::Unopened));
+    }
+
+    #[test]
+    fn format_date() {
+        assert_eq!(super::format_date("%Y-%m-%d").len(), 10);
+        assert_eq!(super::format_date("plain"), "plain");
+    }
+}
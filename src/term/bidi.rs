@@ -0,0 +1,180 @@
+// Copyright 2016 Joe Wilm, The Alacritty Project Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+//! Display reordering of bidirectional text
+//!
+//! The grid always holds text in logical order. Lines containing right-to-left
+//! characters are run through the Unicode bidirectional algorithm to find the
+//! column each cell is displayed at.
+use unicode_bidi::{bidi_class, BidiClass, BidiInfo, Level};
+
+use grid::Row;
+use index::Column;
+use term::cell::{Cell, Flags};
+
+/// Mapping between the logical and visual columns of a single line
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LineOrder {
+    /// Visual column of every logical column
+    visual: Vec<Column>,
+
+    /// Logical column of every visual column
+    logical: Vec<Column>,
+
+    /// Whether the cell in every logical column is part of a right-to-left run
+    rtl: Vec<bool>,
+}
+
+impl LineOrder {
+    /// Compute the display order of a line
+    ///
+    /// Returns `None` if the line has no right-to-left text, in which case the
+    /// visual order is the same as the logical order.
+    pub fn new(row: &Row<Cell>) -> Option<LineOrder> {
+        if !row.iter().any(|cell| is_rtl(cell.c)) {
+            return None;
+        }
+
+        // Spacers are left out of the text so they always follow their wide
+        // character, whichever direction it is displayed in.
+        let mut text = String::with_capacity(row.len());
+        let mut offsets = Vec::with_capacity(row.len());
+        let mut columns = Vec::with_capacity(row.len());
+        for (col, cell) in row.iter().enumerate() {
+            if is_spacer(row, col) {
+                continue;
+            }
+            offsets.push(text.len());
+            columns.push(Column(col));
+            text.push(cell.c);
+        }
+
+        let info = BidiInfo::new(&text, Some(Level::ltr()));
+        let mut logical = Vec::with_capacity(row.len());
+        let mut rtl = vec![false; row.len()];
+        for para in &info.paragraphs {
+            let (levels, runs) = info.visual_runs(para, para.range.clone());
+            for run in runs {
+                let start = offsets.binary_search(&run.start).unwrap_or_else(|i| i);
+                let end = offsets.binary_search(&run.end).unwrap_or_else(|i| i);
+                let cells = &columns[start..end];
+                let run_rtl = levels[run.start].is_rtl();
+
+                let mut push = |col: Column| {
+                    logical.push(col);
+                    rtl[col.0] = run_rtl;
+                    if is_spacer(row, col.0 + 1) {
+                        logical.push(col + 1);
+                        rtl[col.0 + 1] = run_rtl;
+                    }
+                };
+
+                if run_rtl {
+                    cells.iter().rev().cloned().for_each(&mut push);
+                } else {
+                    cells.iter().cloned().for_each(&mut push);
+                }
+            }
+        }
+
+        let mut visual = vec![Column(0); logical.len()];
+        for (visual_col, logical_col) in logical.iter().enumerate() {
+            visual[logical_col.0] = Column(visual_col);
+        }
+
+        Some(LineOrder { visual, logical, rtl })
+    }
+
+    /// Column at which the cell in logical column `col` is displayed
+    #[inline]
+    pub fn visual(&self, col: Column) -> Column {
+        self.visual.get(col.0).cloned().unwrap_or(col)
+    }
+
+    /// Logical column of the cell displayed at column `col`
+    #[inline]
+    pub fn logical(&self, col: Column) -> Column {
+        self.logical.get(col.0).cloned().unwrap_or(col)
+    }
+
+    /// Whether the cell in logical column `col` is displayed right to left
+    #[inline]
+    pub fn is_rtl(&self, col: Column) -> bool {
+        self.rtl.get(col.0).cloned().unwrap_or(false)
+    }
+}
+
+/// Whether `c` makes the surrounding text run right-to-left
+fn is_rtl(c: char) -> bool {
+    match bidi_class(c) {
+        BidiClass::R | BidiClass::AL | BidiClass::RLE | BidiClass::RLO | BidiClass::RLI => true,
+        _ => false,
+    }
+}
+
+/// Whether `col` holds the spacer of the wide character before it
+#[inline]
+fn is_spacer(row: &Row<Cell>, col: usize) -> bool {
+    col > 0 && col < row.len()
+        && row[Column(col)].flags.contains(Flags::WIDE_CHAR_SPACER)
+        && row[Column(col - 1)].flags.contains(Flags::WIDE_CHAR)
+}
+
+#[cfg(test)]
+mod tests {
+    use grid::Row;
+    use index::Column;
+    use term::cell::{Cell, Flags};
+
+    use super::LineOrder;
+
+    fn row(text: &str) -> Row<Cell> {
+        let mut row = Row::new(Column(text.chars().count()), &Cell::default());
+        for (cell, c) in row.iter_mut().zip(text.chars()) {
+            cell.c = c;
+        }
+        row
+    }
+
+    #[test]
+    fn ltr_line_is_not_reordered() {
+        assert_eq!(LineOrder::new(&row("hello world")), None);
+    }
+
+    #[test]
+    fn rtl_run_is_reversed() {
+        let order = LineOrder::new(&row("ab \u{5d0}\u{5d1}\u{5d2} cd")).unwrap();
+
+        let visual = (0..9).map(|col| order.visual(Column(col)).0).collect::<Vec<_>>();
+        assert_eq!(visual, vec![0, 1, 2, 5, 4, 3, 6, 7, 8]);
+
+        for col in 0..9 {
+            assert_eq!(order.logical(order.visual(Column(col))), Column(col));
+            assert_eq!(order.is_rtl(Column(col)), col >= 3 && col <= 5);
+        }
+    }
+
+    #[test]
+    fn wide_char_spacer_follows_its_char() {
+        let mut row = row("\u{5d0}\u{5d1}xy");
+        row[Column(2)].flags.insert(Flags::WIDE_CHAR);
+        row[Column(3)].flags.insert(Flags::WIDE_CHAR_SPACER);
+
+        let order = LineOrder::new(&row).unwrap();
+        assert_eq!(order.logical(Column(0)), Column(1));
+        assert_eq!(order.logical(Column(1)), Column(0));
+        assert_eq!(order.logical(Column(2)), Column(2));
+        assert_eq!(order.logical(Column(3)), Column(3));
+    }
+}
@@ -11,10 +11,22 @@
 // WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
 // See the License for the specific language governing permissions and
 // limitations under the License.
+use std::collections::HashMap;
+use std::hash::BuildHasherDefault;
+use std::mem;
+
+use fnv::FnvHasher;
+
 use ansi::{NamedColor, Color};
 use grid;
 use index::Column;
 
+/// Maximum number of zero-width characters attached to a single cell
+pub const MAX_ZEROWIDTH_CHARS: usize = 8;
+
+/// Maximum number of distinct clusters kept in a `ZeroWidthStorage`
+const MAX_CLUSTERS: usize = 0xffff;
+
 bitflags! {
     #[derive(Serialize, Deserialize)]
     pub struct Flags: u32 {
@@ -27,6 +39,7 @@ bitflags! {
         const WIDE_CHAR_SPACER  = 0b0100_0000;
         const DIM               = 0b1000_0000;
         const DIM_BOLD          = 0b1000_0010;
+        const PROTECTED         = 0b1_0000_0000;
     }
 }
 
@@ -36,6 +49,16 @@ pub struct Cell {
     pub fg: Color,
     pub bg: Color,
     pub flags: Flags,
+
+    /// Index of the zero-width characters following `c` in a `ZeroWidthStorage`
+    ///
+    /// Zero means the cell holds a single character.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub zerowidth: u16,
+}
+
+fn is_zero(value: &u16) -> bool {
+    *value == 0
 }
 
 impl Default for Cell {
@@ -96,6 +119,7 @@ impl Cell {
             bg,
             fg,
             flags: Flags::empty(),
+            zerowidth: 0,
         }
     }
 
@@ -113,9 +137,91 @@ impl Cell {
     }
 }
 
+/// Out-of-line storage for grapheme clusters
+///
+/// Combining characters, variation selectors and zero width joiners are
+/// attached to the preceding cell instead of occupying a cell of their own. To
+/// keep `Cell` small and `Copy`, the zero-width part of every cluster is
+/// interned here and cells only hold its index.
+#[derive(Default)]
+pub struct ZeroWidthStorage {
+    clusters: Vec<Vec<char>>,
+    indices: HashMap<Vec<char>, u16, BuildHasherDefault<FnvHasher>>,
+}
+
+impl ZeroWidthStorage {
+    /// Get the zero-width characters following the cell's character
+    #[inline]
+    pub fn get(&self, cell: &Cell) -> &[char] {
+        match cell.zerowidth {
+            0 => &[],
+            index => &self.clusters[index as usize - 1],
+        }
+    }
+
+    /// Check if no more clusters can be added
+    #[inline]
+    pub fn is_full(&self) -> bool {
+        self.clusters.len() >= MAX_CLUSTERS
+    }
+
+    /// Append a zero-width character to the cell's cluster
+    ///
+    /// The character is dropped if the cluster is already at its maximum
+    /// length or the storage is full.
+    pub fn push(&mut self, cell: &mut Cell, c: char) {
+        let mut cluster = self.get(cell).to_vec();
+        if cluster.len() >= MAX_ZEROWIDTH_CHARS {
+            return;
+        }
+        cluster.push(c);
+
+        if let Some(index) = self.indices.get(&cluster) {
+            cell.zerowidth = *index;
+            return;
+        }
+
+        if self.clusters.len() >= MAX_CLUSTERS {
+            debug!("Dropping zero-width character {:?}; cluster storage is full", c);
+            return;
+        }
+
+        self.clusters.push(cluster.clone());
+        let index = self.clusters.len() as u16;
+        self.indices.insert(cluster, index);
+        cell.zerowidth = index;
+    }
+
+    /// Drop every cluster none of `cells` refers to
+    ///
+    /// Clusters are renumbered, so `cells` has to include every cell using
+    /// this storage.
+    pub fn retain<'a, I>(&mut self, cells: I)
+        where I: Iterator<Item = &'a mut Cell>
+    {
+        let clusters = mem::replace(&mut self.clusters, Vec::new());
+        self.indices.clear();
+
+        let mut indices = vec![0u16; clusters.len()];
+        for cell in cells {
+            if cell.zerowidth == 0 {
+                continue;
+            }
+
+            let old = cell.zerowidth as usize - 1;
+            if indices[old] == 0 {
+                self.clusters.push(clusters[old].clone());
+                indices[old] = self.clusters.len() as u16;
+                self.indices.insert(clusters[old].clone(), indices[old]);
+            }
+            cell.zerowidth = indices[old];
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{Cell, LineLength};
+    use super::{Cell, LineLength, ZeroWidthStorage};
 
     use grid::Row;
     use index::Column;
@@ -137,6 +243,50 @@ mod tests {
 
         assert_eq!(row.line_length(), Column(10));
     }
+
+    #[test]
+    fn zerowidth_clusters_are_shared() {
+        let mut storage = ZeroWidthStorage::default();
+        let mut a = Cell::default();
+        let mut b = Cell::default();
+
+        storage.push(&mut a, '\u{301}');
+        storage.push(&mut b, '\u{301}');
+        storage.push(&mut b, '\u{302}');
+
+        assert_eq!(storage.get(&Cell::default()), &[]);
+        assert_eq!(storage.get(&a), &['\u{301}']);
+        assert_eq!(storage.get(&b), &['\u{301}', '\u{302}']);
+
+        let mut c = Cell::default();
+        storage.push(&mut c, '\u{301}');
+        assert_eq!(c.zerowidth, a.zerowidth);
+    }
+
+    #[test]
+    fn unused_zerowidth_clusters_are_dropped() {
+        let mut storage = ZeroWidthStorage::default();
+        let mut cells = [Cell::default(); 3];
+        storage.push(&mut cells[0], '\u{301}');
+        storage.push(&mut cells[1], '\u{302}');
+        storage.push(&mut cells[2], '\u{303}');
+
+        // The first cell was overwritten
+        cells[0] = Cell::default();
+        storage.retain(cells.iter_mut());
+
+        assert_eq!(storage.clusters.len(), 2);
+        assert_eq!(storage.get(&cells[0]), &[]);
+        assert_eq!(storage.get(&cells[1]), &['\u{302}']);
+        assert_eq!(storage.get(&cells[2]), &['\u{303}']);
+
+        // Dropped clusters can be interned again
+        let mut d = Cell::default();
+        storage.push(&mut d, '\u{303}');
+        assert_eq!(d.zerowidth, cells[2].zerowidth);
+        storage.push(&mut d, '\u{301}');
+        assert_eq!(storage.get(&d), &['\u{303}', '\u{301}']);
+    }
 }
 
 #[cfg(all(test, feature = "bench"))]
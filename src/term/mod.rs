@@ -16,7 +16,10 @@
 use std::ops::{Range, Index, IndexMut};
 use std::ptr;
 use std::cmp::{min, max};
+use std::fmt;
 use std::io;
+use std::iter;
+use std::slice;
 use std::time::{Duration, Instant};
 
 use arraydeque::ArrayDeque;
@@ -24,17 +27,51 @@ use unicode_width::UnicodeWidthChar;
 
 use font::{self, Size};
 use ansi::{self, Color, NamedColor, Attr, Handler, CharsetIndex, StandardCharset, CursorStyle};
+use ansi::C1;
 use grid::{BidirectionalIterator, Grid, ClearRegion, ToRange, Indexed};
-use index::{self, Point, Column, Line, Linear, IndexRange, Contains, RangeInclusive};
+use index::{self, Point, Column, Line, Linear, IndexRange, Contains, RangeInclusive, Side};
 use selection::{self, Span, Selection};
 use config::{Config, VisualBellAnimation};
+use encoding::Encoding;
 use {MouseCursor, Rgb};
 use copypasta::{Clipboard, Load, Store};
 
+pub mod bidi;
 pub mod cell;
 pub mod color;
+pub mod printer;
 pub use self::cell::Cell;
-use self::cell::LineLength;
+use self::bidi::LineOrder;
+use self::cell::{LineLength, ZeroWidthStorage};
+use self::printer::Printer;
+
+/// Joins the characters around it into a single grapheme cluster
+const ZERO_WIDTH_JOINER: char = '\u{200d}';
+
+/// VS15, requests text presentation of the preceding character
+const TEXT_PRESENTATION_SELECTOR: char = '\u{fe0e}';
+
+/// VS16, requests emoji presentation of the preceding character
+const EMOJI_PRESENTATION_SELECTOR: char = '\u{fe0f}';
+
+/// Error character displayed in place of a sequence cancelled by SUB
+const SUBSTITUTE_CHAR: char = '\u{2426}';
+
+/// Convert a version like `0.1.2` to the single number `102`
+///
+/// This is the version reported by the secondary device attributes.
+fn version_number(version: &str) -> usize {
+    version.split('.')
+        .take(3)
+        .map(|part| {
+            part.chars()
+                .take_while(|c| c.is_digit(10))
+                .collect::<String>()
+                .parse::<usize>()
+                .unwrap_or(0)
+        })
+        .fold(0, |number, part| number * 100 + part)
+}
 
 impl selection::SemanticSearch for Term {
     fn semantic_search_left(&self, mut point: Point) -> Point {
@@ -102,8 +139,12 @@ pub struct RenderableCellsIter<'a> {
     column: Column,
     config: &'a Config,
     colors: &'a color::List,
+    zerowidth: &'a ZeroWidthStorage,
     selection: Option<RangeInclusive<index::Linear>>,
     cursor_cells: ArrayDeque<[Indexed<Cell>; 3]>,
+    bidi: bool,
+    line_order: Option<(Line, Option<LineOrder>)>,
+    line_offset: Line,
 }
 
 impl<'a> RenderableCellsIter<'a> {
@@ -115,10 +156,12 @@ impl<'a> RenderableCellsIter<'a> {
         grid: &'b Grid<Cell>,
         cursor: &'b Point,
         colors: &'b color::List,
+        zerowidth: &'b ZeroWidthStorage,
         mode: TermMode,
         config: &'b Config,
         selection: Option<RangeInclusive<index::Linear>>,
         cursor_style: CursorStyle,
+        bidi: bool,
     ) -> RenderableCellsIter<'b> {
         let cursor_index = Linear(cursor.line.0 * grid.num_cols().0 + cursor.col.0);
 
@@ -132,7 +175,11 @@ impl<'a> RenderableCellsIter<'a> {
             selection,
             config,
             colors,
+            zerowidth,
             cursor_cells: ArrayDeque::new(),
+            bidi,
+            line_order: None,
+            line_offset: Line(0),
         }.initialize(cursor_style)
     }
 
@@ -197,6 +244,7 @@ impl<'a> RenderableCellsIter<'a> {
         let mut cursor_cell = self.grid[self.cursor];
         let cursor_color = self.text_cursor_color(&cursor_cell);
         cursor_cell.c = cursor_cell_char;
+        cursor_cell.zerowidth = 0;
         cursor_cell.fg = cursor_color;
 
         let mut wide_cell = cursor_cell;
@@ -268,11 +316,44 @@ impl<'a> RenderableCellsIter<'a> {
         self.mode.contains(mode::TermMode::SHOW_CURSOR) && self.grid.contains(self.cursor)
     }
 
+    /// Column at which a cell of `line` is displayed
+    fn visual_column(&mut self, line: Line, column: Column) -> Column {
+        if !self.bidi {
+            return column;
+        }
+
+        // The order is only computed once for every line
+        let stale = self.line_order.as_ref().map_or(true, |&(order_line, _)| order_line != line);
+        if stale {
+            self.line_order = Some((line, LineOrder::new(&self.grid[line])));
+        }
+
+        match self.line_order {
+            Some((_, Some(ref order))) => order.visual(column),
+            _ => column,
+        }
+    }
+
+    /// Swap the default colors while the screen is in reverse video mode
+    #[inline]
+    fn screen_color(&self, color: NamedColor) -> NamedColor {
+        if !self.mode.contains(mode::TermMode::REVERSE_VIDEO) {
+            return color;
+        }
+
+        match color {
+            NamedColor::Foreground => NamedColor::Background,
+            NamedColor::Background => NamedColor::Foreground,
+            color => color,
+        }
+    }
+
     fn compute_fg_rgb(&self, fg: &Color, cell: &Cell) -> Rgb {
         use self::cell::Flags;
         match *fg {
             Color::Spec(rgb) => rgb,
             Color::Named(ansi) => {
+                let ansi = self.screen_color(ansi);
                 match (self.config.draw_bold_text_with_bright_colors(), cell.flags & Flags::DIM_BOLD) {
                     // Draw bold text in bright colors *and* contains bold flag.
                     (true, self::cell::Flags::DIM_BOLD) |
@@ -311,24 +392,40 @@ impl<'a> RenderableCellsIter<'a> {
     fn compute_bg_rgb(&self, bg: &Color) -> Rgb {
         match *bg {
             Color::Spec(rgb) => rgb,
-            Color::Named(ansi) => self.colors[ansi],
+            Color::Named(ansi) => self.colors[self.screen_color(ansi)],
             Color::Indexed(idx) => self.colors[idx],
         }
     }
 }
 
-pub struct RenderableCell {
+pub struct RenderableCell<'a> {
     pub line: Line,
     pub column: Column,
     pub c: char,
+    /// Zero-width characters following `c` in its grapheme cluster
+    pub zerowidth: &'a [char],
     pub fg: Rgb,
     pub bg: Rgb,
     pub bg_alpha: f32,
     pub flags: cell::Flags,
 }
 
+impl<'a> RenderableCell<'a> {
+    /// Zero-width characters drawn on top of `c`
+    ///
+    /// Joiners and variation selectors only affect the width of the cluster
+    /// and have no glyph of their own.
+    pub fn zerowidth_glyphs(&self) -> iter::Filter<slice::Iter<'a, char>, fn(&&char) -> bool> {
+        fn has_glyph(c: &&char) -> bool {
+            **c != ZERO_WIDTH_JOINER && !('\u{fe00}' <= **c && **c <= '\u{fe0f}')
+        }
+
+        self.zerowidth.iter().filter(has_glyph)
+    }
+}
+
 impl<'a> Iterator for RenderableCellsIter<'a> {
-    type Item = RenderableCell;
+    type Item = RenderableCell<'a>;
 
     /// Gets the next renderable cell
     ///
@@ -382,8 +479,8 @@ impl<'a> Iterator for RenderableCellsIter<'a> {
 
                 if invert {
                     if cell.fg == cell.bg {
-                        bg_rgb = self.colors[NamedColor::Foreground];
-                        fg_rgb = self.colors[NamedColor::Background];
+                        bg_rgb = self.colors[self.screen_color(NamedColor::Foreground)];
+                        fg_rgb = self.colors[self.screen_color(NamedColor::Background)];
                         bg_alpha = 1.0
                     } else {
                         bg_rgb = self.compute_fg_rgb(&cell.fg, &cell);
@@ -395,11 +492,15 @@ impl<'a> Iterator for RenderableCellsIter<'a> {
                     bg_alpha = self.compute_bg_alpha(&cell.bg);
                 }
 
+                let column = self.visual_column(line, column);
+                let zerowidth: &'a ZeroWidthStorage = self.zerowidth;
+
                 return Some(RenderableCell {
-                    line,
+                    line: line + self.line_offset,
                     column,
                     flags: cell.flags,
                     c: cell.c,
+                    zerowidth: zerowidth.get(&cell),
                     fg: fg_rgb,
                     bg: bg_rgb,
                     bg_alpha,
@@ -417,7 +518,7 @@ impl<'a> Iterator for RenderableCellsIter<'a> {
 
 pub mod mode {
     bitflags! {
-        pub struct TermMode: u16 {
+        pub struct TermMode: u32 {
             const SHOW_CURSOR         = 0b00_0000_0000_0001;
             const APP_CURSOR          = 0b00_0000_0000_0010;
             const APP_KEYPAD          = 0b00_0000_0000_0100;
@@ -432,7 +533,11 @@ pub mod mode {
             const FOCUS_IN_OUT        = 0b00_1000_0000_0000;
             const ALT_SCREEN          = 0b01_0000_0000_0000;
             const MOUSE_DRAG          = 0b10_0000_0000_0000;
-            const ANY                 = 0b11_1111_1111_1111;
+            const EXPLICIT_BIDI       = 0b100_0000_0000_0000;
+            const REVERSE_VIDEO       = 0b1000_0000_0000_0000;
+            const VT52                = 0b1_0000_0000_0000_0000;
+            const VT52_GRAPHICS       = 0b10_0000_0000_0000_0000;
+            const ANY                 = 0b11_1111_1111_1111_1111;
             const NONE                = 0;
         }
     }
@@ -461,16 +566,17 @@ impl CharsetMapping for StandardCharset {
             StandardCharset::Ascii => c,
             StandardCharset::SpecialCharacterAndLineDrawing =>
                 match c {
+                    '_' => ' ',
                     '`' => '◆',
                     'a' => '▒',
-                    'b' => '\t',
-                    'c' => '\u{000c}',
-                    'd' => '\r',
-                    'e' => '\n',
+                    'b' => '\u{2409}',
+                    'c' => '\u{240c}',
+                    'd' => '\u{240d}',
+                    'e' => '\u{240a}',
                     'f' => '°',
                     'g' => '±',
                     'h' => '\u{2424}',
-                    'i' => '\u{000b}',
+                    'i' => '\u{240b}',
                     'j' => '┘',
                     'k' => '┐',
                     'l' => '┌',
@@ -494,10 +600,133 @@ impl CharsetMapping for StandardCharset {
                     '~' => '·',
                     _ => c
                 },
+            StandardCharset::DecSupplemental =>
+                match c {
+                    '(' => '¤',
+                    'W' => 'Œ',
+                    ']' => 'Ÿ',
+                    'w' => 'œ',
+                    '}' => 'ÿ',
+                    '!'...'~' => ::std::char::from_u32(c as u32 + 0x80).unwrap_or(c),
+                    _ => c
+                },
+            StandardCharset::DecTechnical =>
+                match c {
+                    '!' => '⎷',
+                    '"' => '┌',
+                    '#' => '─',
+                    '$' => '⌠',
+                    '%' => '⌡',
+                    '&' => '│',
+                    '\'' => '⎡',
+                    '(' => '⎣',
+                    ')' => '⎤',
+                    '*' => '⎦',
+                    '+' => '⎛',
+                    ',' => '⎝',
+                    '-' => '⎞',
+                    '.' => '⎠',
+                    '/' => '⎨',
+                    '0' => '⎬',
+                    '<' => '≤',
+                    '=' => '≠',
+                    '>' => '≥',
+                    '?' => '∫',
+                    '@' => '∴',
+                    'A' => '∝',
+                    'B' => '∞',
+                    'C' => '÷',
+                    'D' => 'Δ',
+                    'E' => '∇',
+                    'F' => 'Φ',
+                    'G' => 'Γ',
+                    'H' => '∼',
+                    'I' => '≃',
+                    'J' => 'Θ',
+                    'K' => '×',
+                    'L' => 'Λ',
+                    'M' => '⇔',
+                    'N' => '⇒',
+                    'O' => '≡',
+                    'P' => 'Π',
+                    'Q' => 'Ψ',
+                    'S' => 'Σ',
+                    'V' => '√',
+                    'W' => 'Ω',
+                    'X' => 'Ξ',
+                    'Y' => 'Υ',
+                    'Z' => '⊂',
+                    '[' => '⊃',
+                    '\\' => '∩',
+                    ']' => '∪',
+                    '^' => '∧',
+                    '_' => '∨',
+                    '`' => '¬',
+                    'a' => 'α',
+                    'b' => 'β',
+                    'c' => 'χ',
+                    'd' => 'δ',
+                    'e' => 'ε',
+                    'f' => 'φ',
+                    'g' => 'γ',
+                    'h' => 'η',
+                    'i' => 'ι',
+                    'j' => 'θ',
+                    'k' => 'κ',
+                    'l' => 'λ',
+                    'n' => 'ν',
+                    'o' => '∂',
+                    'p' => 'π',
+                    'q' => 'ψ',
+                    'r' => 'ρ',
+                    's' => 'σ',
+                    't' => 'τ',
+                    'v' => 'ƒ',
+                    'w' => 'ω',
+                    'x' => 'ξ',
+                    'y' => 'υ',
+                    'z' => 'ζ',
+                    '{' => '←',
+                    '|' => '↑',
+                    '}' => '→',
+                    '~' => '↓',
+                    _ => c
+                },
+            StandardCharset::British =>
+                match c {
+                    '#' => '£',
+                    _ => c
+                },
+            StandardCharset::Dutch => national(c, "£¾ĳ½|^_`¨ƒ¼´"),
+            StandardCharset::Finnish => national(c, "#@ÄÖÅÜ_éäöåü"),
+            StandardCharset::French => national(c, "£à°ç§^_`éùè¨"),
+            StandardCharset::FrenchCanadian => national(c, "#àâçêî_ôéùèû"),
+            StandardCharset::German => national(c, "#§ÄÖÜ^_`äöüß"),
+            StandardCharset::Italian => national(c, "£§°çé^_ùàòèì"),
+            StandardCharset::NorwegianDanish => national(c, "#ÄÆØÅÜ_äæøåü"),
+            StandardCharset::Portuguese => national(c, "#@ÃÇÕ^_`ãçõ~"),
+            StandardCharset::Spanish => national(c, "£§¡Ñ¿^_`°ñç~"),
+            StandardCharset::Swedish => national(c, "#ÉÄÖÅÜ_éäöåü"),
+            StandardCharset::Swiss => national(c, "ùàéçêîèôäöüû"),
         }
     }
 }
 
+/// Map a character through a national replacement character set
+///
+/// `replacements` holds the characters replacing each of the ASCII characters
+/// in `NATIONAL_CHARS`.
+#[inline]
+fn national(c: char, replacements: &str) -> char {
+    NATIONAL_CHARS.chars()
+        .position(|national| national == c)
+        .and_then(|index| replacements.chars().nth(index))
+        .unwrap_or(c)
+}
+
+/// ASCII characters which may be replaced by a national replacement character set
+const NATIONAL_CHARS: &str = "#@[\\]^_`{|}~";
+
 #[derive(Default, Copy, Clone)]
 struct Charsets([StandardCharset; 4]);
 
@@ -524,6 +753,13 @@ pub struct Cursor {
 
     /// Currently configured graphic character sets
     charsets: Charsets,
+
+    /// The graphic character set, out of `charsets`, which ASCII is currently
+    /// being mapped to
+    active_charset: CharsetIndex,
+
+    /// Character set used for the next character only
+    single_shift: Option<CharsetIndex>,
 }
 
 pub struct VisualBell {
@@ -661,9 +897,19 @@ pub struct Term {
     /// Would be nice to avoid the allocation...
     next_title: Option<String>,
 
+    /// Last title requested by the application, even if it wasn't applied
+    title: Option<String>,
+
+    /// Working directory of the shell, as reported by OSC 7
+    working_directory: Option<String>,
+
     /// Got a request to set the mouse cursor; it's buffered here until the next draw
     next_mouse_cursor: Option<MouseCursor>,
 
+    /// Size of the main display after the status line was shown or hidden;
+    /// it's buffered here until the I/O loop resizes the pty
+    next_pty_size: Option<SizeInfo>,
+
     /// Alternate grid
     alt_grid: Grid<Cell>,
 
@@ -673,10 +919,6 @@ pub struct Term {
     /// The cursor
     cursor: Cursor,
 
-    /// The graphic character set, out of `charsets`, which ASCII is currently
-    /// being mapped to
-    active_charset: CharsetIndex,
-
     /// Tabstops
     tabs: Vec<bool>,
 
@@ -725,6 +967,39 @@ pub struct Term {
 
     /// Number of spaces in one tab
     tabspaces: usize,
+
+    /// Zero-width characters of grapheme clusters in both grids
+    zerowidth: ZeroWidthStorage,
+
+    /// East Asian ambiguous width characters occupy two cells
+    ambiguous_wide: bool,
+
+    /// Variation selectors switch the preceding character's width
+    presentation_selectors: bool,
+
+    /// Reorder right-to-left text for display
+    bidi: bool,
+
+    /// Interpret raw 8-bit C1 controls received from the application
+    c1_controls: bool,
+
+    /// Encoding of C1 controls in replies, selected by S7C1T and S8C1T
+    c1_encoding: ansi::C1Encoding,
+
+    /// Destination of media copy output
+    printer: Printer,
+
+    /// Type of the status line shown below the main display
+    status_line: ansi::StatusLineType,
+
+    /// Contents of the host writable status line
+    status_grid: Grid<Cell>,
+
+    /// Cursor of the status line, used instead of `cursor` while it's active
+    status_cursor: Cursor,
+
+    /// Output is directed to the status line
+    status_active: bool,
 }
 
 /// Terminal size info
@@ -789,11 +1064,28 @@ impl Term {
         self.next_title.take()
     }
 
+    /// Last title requested by the application
+    #[inline]
+    pub fn title(&self) -> Option<&str> {
+        self.title.as_ref().map(String::as_str)
+    }
+
+    /// Working directory last reported by the shell
+    #[inline]
+    pub fn working_directory(&self) -> Option<&str> {
+        self.working_directory.as_ref().map(String::as_str)
+    }
+
     #[inline]
     pub fn get_next_mouse_cursor(&mut self) -> Option<MouseCursor> {
         self.next_mouse_cursor.take()
     }
 
+    #[inline]
+    pub fn get_next_pty_size(&mut self) -> Option<SizeInfo> {
+        self.next_pty_size.take()
+    }
+
     pub fn new(config: &Config, size: SizeInfo) -> Term {
         let template = Cell::default();
 
@@ -809,10 +1101,14 @@ impl Term {
 
         let alt = grid.clone();
         let scroll_region = Line(0)..grid.num_lines();
+        let status_grid = Grid::new(Line(1), num_cols, &template);
 
         Term {
             next_title: None,
+            title: None,
+            working_directory: None,
             next_mouse_cursor: None,
+            next_pty_size: None,
             dirty: false,
             visual_bell: VisualBell::new(config),
             next_is_urgent: None,
@@ -822,7 +1118,6 @@ impl Term {
             alt: false,
             font_size: config.font().size(),
             original_font_size: config.font().size(),
-            active_charset: Default::default(),
             cursor: Default::default(),
             cursor_save: Default::default(),
             cursor_save_alt: Default::default(),
@@ -838,9 +1133,38 @@ impl Term {
             default_cursor_style: config.cursor_style(),
             dynamic_title: config.dynamic_title(),
             tabspaces,
+            zerowidth: Default::default(),
+            ambiguous_wide: config.unicode().ambiguous_wide(),
+            presentation_selectors: config.unicode().presentation_selectors(),
+            bidi: config.unicode().bidi(),
+            c1_controls: config.c1_controls(),
+            c1_encoding: Default::default(),
+            status_line: Default::default(),
+            status_grid,
+            status_cursor: Default::default(),
+            status_active: false,
+            printer: Printer::new(config.printer()),
         }
     }
 
+    /// Write a reply to the application
+    ///
+    /// The sequence starts with the C1 control `introducer`, encoded as
+    /// selected by S7C1T and S8C1T. String controls are terminated with ST.
+    fn reply<W: io::Write>(&self, writer: &mut W, introducer: u8, body: fmt::Arguments) {
+        let _ = self.c1_encoding.write_control(writer, introducer)
+            .and_then(|_| writer.write_fmt(body))
+            .and_then(|_| match introducer {
+                C1::DCS | C1::OSC => self.c1_encoding.write_control(writer, C1::ST),
+                _ => Ok(()),
+            });
+    }
+
+    /// Encoding of the application, used for the data sent to the printer
+    pub fn set_encoding(&mut self, encoding: Encoding) {
+        self.printer.set_encoding(encoding);
+    }
+
     pub fn change_font_size(&mut self, delta: i8) {
         // Saturating addition with minimum font size 1
         let new_size = self.font_size + Size::new(f32::from(delta));
@@ -864,6 +1188,24 @@ impl Term {
         self.visual_bell.update_config(config);
         self.default_cursor_style = config.cursor_style();
         self.dynamic_title = config.dynamic_title();
+        self.ambiguous_wide = config.unicode().ambiguous_wide();
+        self.presentation_selectors = config.unicode().presentation_selectors();
+        self.bidi = config.unicode().bidi();
+        self.c1_controls = config.c1_controls();
+        self.printer.update_config(config.printer());
+    }
+
+    /// Number of cells occupied by a character
+    ///
+    /// Every width decision goes through here; the rest of the terminal reads
+    /// the resulting `WIDE_CHAR` and `WIDE_CHAR_SPACER` flags from the grid.
+    #[inline]
+    pub fn char_width(&self, c: char) -> Option<usize> {
+        if self.ambiguous_wide {
+            c.width_cjk()
+        } else {
+            c.width()
+        }
     }
 
     #[inline]
@@ -892,13 +1234,20 @@ impl Term {
         use std::ops::Range;
 
         trait Append : PushChar {
-            fn append(&mut self, grid: &Grid<Cell>, line: Line, cols: Range<Column>) -> Option<Range<Column>>;
+            fn append(
+                &mut self,
+                grid: &Grid<Cell>,
+                zerowidth: &ZeroWidthStorage,
+                line: Line,
+                cols: Range<Column>
+            ) -> Option<Range<Column>>;
         }
 
         impl Append for String {
             fn append(
                 &mut self,
                 grid: &Grid<Cell>,
+                zerowidth: &ZeroWidthStorage,
                 line: Line,
                 cols: Range<Column>
             ) -> Option<Range<Column>> {
@@ -912,6 +1261,7 @@ impl Term {
                     for cell in &grid_line[cols.start..line_end] {
                         if !cell.flags.contains(cell::Flags::WIDE_CHAR_SPACER) {
                             self.push(cell.c);
+                            self.extend(zerowidth.get(cell));
                         }
                     }
 
@@ -935,30 +1285,30 @@ impl Term {
         match line_count {
             // Selection within single line
             Line(0) => {
-                res.append(&self.grid, start.line, start.col..end.col);
+                res.append(&self.grid, &self.zerowidth, start.line, start.col..end.col);
             },
 
             // Selection ends on line following start
             Line(1) => {
                 // Starting line
-                res.append(&self.grid, start.line, start.col..max_col);
+                res.append(&self.grid, &self.zerowidth, start.line, start.col..max_col);
 
                 // Ending line
-                res.append(&self.grid, end.line, Column(0)..end.col);
+                res.append(&self.grid, &self.zerowidth, end.line, Column(0)..end.col);
             },
 
             // Multi line selection
             _ => {
                 // Starting line
-                res.append(&self.grid, start.line, start.col..max_col);
+                res.append(&self.grid, &self.zerowidth, start.line, start.col..max_col);
 
                 let middle_range = IndexRange::from((start.line + 1)..(end.line));
                 for line in middle_range {
-                    res.append(&self.grid, line, Column(0)..max_col);
+                    res.append(&self.grid, &self.zerowidth, line, Column(0)..max_col);
                 }
 
                 // Ending line
-                res.append(&self.grid, end.line, Column(0)..end.col);
+                res.append(&self.grid, &self.zerowidth, end.line, Column(0)..end.col);
             }
         }
 
@@ -975,6 +1325,52 @@ impl Term {
         self.size_info().pixels_to_coords(x, y)
     }
 
+    /// Convert a point on the screen to the position of the cell displayed there
+    ///
+    /// This only differs from the screen position on lines which were reordered
+    /// for display by the bidi algorithm.
+    pub fn logical_point(&self, point: Point) -> Point {
+        match self.line_order(point.line) {
+            Some(order) => Point { line: point.line, col: order.logical(point.col) },
+            None => point,
+        }
+    }
+
+    /// Convert a point and cell side on the screen to a selection position
+    ///
+    /// Cells of right-to-left runs are displayed mirrored, so the left half
+    /// of such a cell on the screen is its end in logical order.
+    pub fn selection_point(&self, point: Point, side: Side) -> (Point, Side) {
+        let order = match self.line_order(point.line) {
+            Some(order) => order,
+            None => return (point, side),
+        };
+
+        let col = order.logical(point.col);
+        let side = match side {
+            Side::Left if order.is_rtl(col) => Side::Right,
+            Side::Right if order.is_rtl(col) => Side::Left,
+            side => side,
+        };
+
+        (Point { line: point.line, col }, side)
+    }
+
+    /// Whether lines are reordered for display
+    #[inline]
+    fn bidi_active(&self) -> bool {
+        self.bidi && !self.mode.contains(mode::TermMode::EXPLICIT_BIDI)
+    }
+
+    /// Display order of a line, if it differs from the logical order
+    fn line_order(&self, line: Line) -> Option<LineOrder> {
+        if self.bidi_active() && line < self.grid.num_lines() {
+            LineOrder::new(&self.grid[line])
+        } else {
+            None
+        }
+    }
+
     /// Access to the raw grid data structure
     ///
     /// This is a bit of a hack; when the window is closed, the event processor
@@ -996,23 +1392,156 @@ impl Term {
     ) -> RenderableCellsIter {
         let selection = selection.and_then(|s| s.to_span(self))
             .map(|span| span.to_range());
-        let cursor = if window_focused {
-            self.cursor_style.unwrap_or(self.default_cursor_style)
-        } else {
-            CursorStyle::HollowBlock
-        };
+        let cursor = self.render_cursor_style(window_focused);
+
+        // The cursor is drawn on the status line while it's active
+        let mut mode = self.mode;
+        if self.status_active {
+            mode.remove(mode::TermMode::SHOW_CURSOR);
+        }
 
         RenderableCellsIter::new(
             &self.grid,
             &self.cursor.point,
             &self.colors,
-            self.mode,
+            &self.zerowidth,
+            mode,
             config,
             selection,
             cursor,
+            self.bidi_active(),
         )
     }
 
+    /// Iterate over the renderable cells of the status line
+    ///
+    /// The status line is placed below the main display. Returns `None` when
+    /// no status line is shown.
+    pub fn renderable_status_line<'b>(
+        &'b mut self,
+        config: &'b Config,
+        window_focused: bool,
+    ) -> Option<RenderableCellsIter<'b>> {
+        match self.status_line {
+            ansi::StatusLineType::None => return None,
+            ansi::StatusLineType::Indicator => self.update_status_indicator(),
+            ansi::StatusLineType::HostWritable => (),
+        }
+
+        let cursor = self.render_cursor_style(window_focused);
+
+        let mut mode = self.mode;
+        if !self.status_active {
+            mode.remove(mode::TermMode::SHOW_CURSOR);
+        }
+
+        let mut iter = RenderableCellsIter::new(
+            &self.status_grid,
+            &self.status_cursor.point,
+            &self.colors,
+            &self.zerowidth,
+            mode,
+            config,
+            None,
+            cursor,
+            false,
+        );
+        iter.line_offset = self.grid.num_lines();
+        Some(iter)
+    }
+
+    fn render_cursor_style(&self, window_focused: bool) -> CursorStyle {
+        if window_focused {
+            self.cursor_style.unwrap_or(self.default_cursor_style)
+        } else {
+            CursorStyle::HollowBlock
+        }
+    }
+
+    /// Write the cursor position to the indicator status line
+    fn update_status_indicator(&mut self) {
+        let point = self.cursor.point;
+        let text = format!("Ln {}, Col {}", point.line.0 + 1, point.col.0 + 1);
+
+        let mut template = Cell::default();
+        template.flags.insert(cell::Flags::INVERSE);
+
+        let mut chars = text.chars();
+        for cell in &mut self.status_grid[Line(0)] {
+            cell.reset(&template);
+            if let Some(c) = chars.next() {
+                cell.c = c;
+            }
+        }
+    }
+
+    /// Number of lines taken up by the status line
+    #[inline]
+    pub fn status_line_rows(&self) -> usize {
+        match self.status_line {
+            ansi::StatusLineType::None => 0,
+            _ => self.status_grid.num_lines().0,
+        }
+    }
+
+    /// Size of the main display, excluding the status line
+    ///
+    /// This is the size reported to the pty.
+    pub fn pty_size_info(&self) -> SizeInfo {
+        let mut size = self.size_info;
+        size.height -= self.status_line_rows() as f32 * size.cell_height;
+        size
+    }
+
+    /// Cursor output is written at, which is on the status line while it's active
+    fn active_cursor_mut(&mut self) -> &mut Cursor {
+        if self.status_active {
+            &mut self.status_cursor
+        } else {
+            &mut self.cursor
+        }
+    }
+
+    /// Grid and cursor of the display output is directed to
+    fn active_display_mut(&mut self) -> (&mut Grid<Cell>, &Cursor) {
+        if self.status_active {
+            (&mut self.status_grid, &self.status_cursor)
+        } else {
+            (&mut self.grid, &self.cursor)
+        }
+    }
+
+    /// Write a character to the status line
+    ///
+    /// The status line is a single line which doesn't wrap; characters
+    /// written at the last column replace each other.
+    fn status_input(&mut self, c: char) {
+        let width = match self.char_width(c) {
+            Some(width) if width > 0 => width,
+            _ => return,
+        };
+
+        // A wide character doesn't fit on a single column status line
+        let num_cols = self.status_grid.num_cols();
+        if width > num_cols.0 {
+            return;
+        }
+
+        let col = min(self.status_cursor.point.col, Column(num_cols.0.saturating_sub(width)));
+        let template = self.status_cursor.template;
+        let row = &mut self.status_grid[Line(0)];
+
+        row[col] = template;
+        row[col].c = c;
+        if width == 2 {
+            row[col].flags.insert(cell::Flags::WIDE_CHAR);
+            row[col + 1] = template;
+            row[col + 1].flags.insert(cell::Flags::WIDE_CHAR_SPACER);
+        }
+
+        self.status_cursor.point.col = min(col + width, num_cols - 1);
+    }
+
     /// Resize terminal to new dimensions
     pub fn resize(&mut self, size : &SizeInfo) {
         debug!("Term::resize");
@@ -1027,7 +1556,7 @@ impl Term {
         let old_cols = self.grid.num_cols();
         let old_lines = self.grid.num_lines();
         let mut num_cols = size.cols();
-        let mut num_lines = size.lines();
+        let mut num_lines = Line(size.lines().0.saturating_sub(self.status_line_rows()));
 
         self.size_info = *size;
 
@@ -1068,6 +1597,8 @@ impl Term {
         let template = Cell::default();
         self.grid.resize(num_lines, num_cols, &template);
         self.alt_grid.resize(num_lines, num_cols, &template);
+        self.status_grid.resize(Line(1), num_cols, &template);
+        self.status_cursor.point.col = min(self.status_cursor.point.col, num_cols - 1);
 
         // Reset scrolling region to new size
         self.scroll_region = Line(0)..self.grid.num_lines();
@@ -1110,15 +1641,35 @@ impl Term {
     }
 
     pub fn swap_alt(&mut self) {
-        if self.alt {
-            let template = &self.cursor.template;
-            self.grid.clear(|c| c.reset(template));
-        }
-
         self.alt = !self.alt;
         ::std::mem::swap(&mut self.grid, &mut self.alt_grid);
     }
 
+    /// Switch to the alternate screen, clearing it first if `clear` is set
+    fn enter_alt_screen(&mut self, clear: bool) {
+        self.mode.insert(mode::TermMode::ALT_SCREEN);
+        if !self.alt {
+            self.swap_alt();
+            if clear {
+                let template = self.cursor.template;
+                self.grid.clear(|c| c.reset(&template));
+            }
+        }
+    }
+
+    /// Switch back to the primary screen, clearing the alternate screen on the
+    /// way out if `clear` is set
+    fn exit_alt_screen(&mut self, clear: bool) {
+        self.mode.remove(mode::TermMode::ALT_SCREEN);
+        if self.alt {
+            if clear {
+                let template = self.cursor.template;
+                self.grid.clear(|c| c.reset(&template));
+            }
+            self.swap_alt();
+        }
+    }
+
     /// Scroll screen down
     ///
     /// Text moves down; clear at bottom
@@ -1174,72 +1725,283 @@ impl Term {
         self.grid.clear(|c| c.reset(&template));
     }
 
-    #[inline]
-    pub fn background_color(&self) -> Rgb {
-        self.colors[NamedColor::Background]
+    /// Drop the zero-width clusters no longer used by any cell
+    fn prune_zerowidth(&mut self) {
+        let cells = self.grid.lines_mut()
+            .chain(self.alt_grid.lines_mut())
+            .chain(self.status_grid.lines_mut())
+            .flat_map(|row| row.cells_mut());
+        self.zerowidth.retain(cells);
     }
-}
 
-impl ansi::TermInfo for Term {
-    #[inline]
-    fn lines(&self) -> Line {
-        self.grid.num_lines()
-    }
+    /// Location of the cell holding the most recently printed character
+    ///
+    /// Zero-width characters are attached to this cell. Returns `None` if the
+    /// cursor is at the start of a line.
+    fn preceding_cell(&self) -> Option<Point> {
+        let mut point = self.cursor.point;
+        if !self.input_needs_wrap {
+            if point.col == Column(0) {
+                return None;
+            }
+            point.col -= 1;
+        }
 
-    #[inline]
-    fn cols(&self) -> Column {
-        self.grid.num_cols()
+        if point.col > Column(0) && self.grid[&point].flags.contains(cell::Flags::WIDE_CHAR_SPACER) {
+            point.col -= 1;
+        }
+
+        Some(point)
     }
-}
 
-impl ansi::Handler for Term {
-    /// Set the window title
-    #[inline]
-    fn set_title(&mut self, title: &str) {
-        if self.dynamic_title {
-            self.next_title = Some(title.to_owned());
+    /// Switch the most recently printed character to wide emoji presentation
+    fn widen_cell(&mut self, point: Point) {
+        // Without a cell to the right there is no room to widen the character
+        if self.input_needs_wrap || self.grid[&point].flags.contains(cell::Flags::WIDE_CHAR) {
+            return;
         }
-    }
 
-    /// Set the mouse cursor
-    #[inline]
-    fn set_mouse_cursor(&mut self, cursor: MouseCursor) {
-        self.next_mouse_cursor = Some(cursor);
+        self.grid[&point].flags.insert(cell::Flags::WIDE_CHAR);
+
+        let spacer = &mut self.grid[&self.cursor.point];
+        *spacer = self.cursor.template;
+        spacer.flags.insert(cell::Flags::WIDE_CHAR_SPACER);
+
+        if (self.cursor.point.col + 1) < self.grid.num_cols() {
+            self.cursor.point.col += 1;
+        } else {
+            self.input_needs_wrap = true;
+        }
     }
 
-    /// A character to be displayed
-    #[inline]
-    fn input(&mut self, c: char) {
-        if self.input_needs_wrap {
-            if !self.mode.contains(mode::TermMode::LINE_WRAP) {
-                return;
-            }
+    /// Switch the most recently printed character to narrow text presentation
+    fn narrow_cell(&mut self, point: Point) {
+        if !self.grid[&point].flags.contains(cell::Flags::WIDE_CHAR) ||
+            point.col + 1 >= self.grid.num_cols()
+        {
+            return;
+        }
 
-            trace!("wrapping");
+        self.grid[&point].flags.remove(cell::Flags::WIDE_CHAR);
 
-            {
-                let location = Point {
-                    line: self.cursor.point.line,
-                    col: self.cursor.point.col
-                };
+        let template = self.cursor.template;
+        let spacer = Point::new(point.line, point.col + 1);
+        self.grid[&spacer].reset(&template);
 
-                let cell = &mut self.grid[&location];
-                cell.flags.insert(cell::Flags::WRAPLINE);
-            }
+        self.cursor.point.col = spacer.col;
+        self.input_needs_wrap = false;
+    }
 
-            if (self.cursor.point.line + 1) >= self.scroll_region.end {
-                self.linefeed();
-            } else {
-                self.cursor.point.line += 1;
-            }
+    /// Erase part of the current line
+    ///
+    /// When `selective` is set, cells protected by DECSCA are left untouched.
+    fn erase_line(&mut self, mode: ansi::LineClearMode, selective: bool) {
+        let (grid, cursor) = self.active_display_mut();
+        let mut template = cursor.template;
+        template.flags ^= template.flags;
 
-            self.cursor.point.col = Column(0);
-            self.input_needs_wrap = false;
+        let col = cursor.point.col;
+        let row = &mut grid[cursor.point.line];
+        let cells = match mode {
+            ansi::LineClearMode::Right => &mut row[col..],
+            ansi::LineClearMode::Left => &mut row[..(col + 1)],
+            ansi::LineClearMode::All => &mut row[..],
+        };
+
+        for cell in cells {
+            if !selective || !cell.flags.contains(cell::Flags::PROTECTED) {
+                cell.reset(&template);
+            }
         }
+    }
 
-        {
-            // Number of cells the char will occupy
-            if let Some(width) = c.width() {
+    /// Erase part of the screen
+    ///
+    /// When `selective` is set, cells protected by DECSCA are left untouched.
+    fn erase_screen(&mut self, mode: ansi::ClearMode, selective: bool) {
+        let (grid, cursor) = self.active_display_mut();
+        let mut template = cursor.template;
+        template.flags ^= template.flags;
+
+        let reset = |cell: &mut Cell| {
+            if !selective || !cell.flags.contains(cell::Flags::PROTECTED) {
+                cell.reset(&template);
+            }
+        };
+
+        match mode {
+            ansi::ClearMode::Below => {
+                for cell in &mut grid[cursor.point.line][cursor.point.col..] {
+                    reset(cell);
+                }
+                if cursor.point.line < grid.num_lines() - 1 {
+                    grid.clear_region((cursor.point.line + 1).., &reset);
+                }
+            },
+            ansi::ClearMode::All => {
+                grid.clear(&reset);
+            },
+            ansi::ClearMode::Above => {
+                // If there are any lines above the cursor
+                if cursor.point.line > Line(0) {
+                    // Fully clear all lines before the current line
+                    grid.clear_region(..cursor.point.line, &reset);
+                }
+                // Clear up to the current column in the current line
+                let end = min(cursor.point.col + 1, grid.num_cols());
+                for cell in &mut grid[cursor.point.line][..end] {
+                    reset(cell);
+                }
+            },
+            // If scrollback is implemented, this should clear it
+            ansi::ClearMode::Saved => return
+        }
+    }
+
+    /// Convert a rectangle relative to the origin into a grid region
+    ///
+    /// The rectangle is clamped to the screen, or to the scroll region when
+    /// origin mode is set. Returns `None` if the resulting area is empty.
+    fn rectangle_region(&self, area: ansi::Rectangle) -> Option<(Range<Line>, Range<Column>)> {
+        let (y_offset, max_y) = if self.mode.contains(mode::TermMode::ORIGIN) {
+            (self.scroll_region.start, self.scroll_region.end - 1)
+        } else {
+            (Line(0), self.grid.num_lines() - 1)
+        };
+        let max_x = self.grid.num_cols() - 1;
+
+        let top = min(area.start.line + y_offset, max_y);
+        let bottom = min(area.end.line + y_offset, max_y);
+        let left = min(area.start.col, max_x);
+        let right = min(area.end.col, max_x);
+
+        if top > bottom || left > right {
+            return None;
+        }
+
+        Some((top..(bottom + 1), left..(right + 1)))
+    }
+
+    #[inline]
+    pub fn background_color(&self) -> Rgb {
+        if self.mode.contains(mode::TermMode::REVERSE_VIDEO) {
+            self.colors[NamedColor::Foreground]
+        } else {
+            self.colors[NamedColor::Background]
+        }
+    }
+}
+
+impl ansi::TermInfo for Term {
+    #[inline]
+    fn lines(&self) -> Line {
+        self.grid.num_lines()
+    }
+
+    #[inline]
+    fn cols(&self) -> Column {
+        self.grid.num_cols()
+    }
+
+    #[inline]
+    fn c1_controls(&self) -> bool {
+        self.c1_controls
+    }
+
+    #[inline]
+    fn vt52_mode(&self) -> bool {
+        self.mode.contains(mode::TermMode::VT52)
+    }
+}
+
+impl ansi::Handler for Term {
+    /// Set the window title
+    #[inline]
+    fn set_title(&mut self, title: &str) {
+        self.title = Some(title.to_owned());
+        if self.dynamic_title {
+            self.next_title = Some(title.to_owned());
+        }
+    }
+
+    #[inline]
+    fn set_working_directory(&mut self, path: &str) {
+        self.working_directory = Some(path.to_owned());
+    }
+
+    /// Set the mouse cursor
+    #[inline]
+    fn set_mouse_cursor(&mut self, cursor: MouseCursor) {
+        self.next_mouse_cursor = Some(cursor);
+    }
+
+    /// A character to be displayed
+    #[inline]
+    fn input(&mut self, c: char) {
+        if self.status_active {
+            return self.status_input(c);
+        }
+
+        // Zero-width characters and characters following a zero width joiner
+        // are part of the preceding cell's grapheme cluster
+        let width = self.char_width(c);
+        if let Some(point) = self.preceding_cell() {
+            let joined = width == Some(0) ||
+                self.zerowidth.get(&self.grid[&point]).last() == Some(&ZERO_WIDTH_JOINER);
+
+            if joined {
+                if self.zerowidth.is_full() {
+                    self.prune_zerowidth();
+                }
+
+                {
+                    let cell = &mut self.grid[&point];
+                    self.zerowidth.push(cell, c);
+                }
+
+                if self.presentation_selectors {
+                    match c {
+                        TEXT_PRESENTATION_SELECTOR => self.narrow_cell(point),
+                        EMOJI_PRESENTATION_SELECTOR => self.widen_cell(point),
+                        _ => (),
+                    }
+                }
+                return;
+            }
+        } else if width == Some(0) {
+            return;
+        }
+
+        if self.input_needs_wrap {
+            if !self.mode.contains(mode::TermMode::LINE_WRAP) {
+                return;
+            }
+
+            trace!("wrapping");
+
+            {
+                let location = Point {
+                    line: self.cursor.point.line,
+                    col: self.cursor.point.col
+                };
+
+                let cell = &mut self.grid[&location];
+                cell.flags.insert(cell::Flags::WRAPLINE);
+            }
+
+            if (self.cursor.point.line + 1) >= self.scroll_region.end {
+                self.linefeed();
+            } else {
+                self.cursor.point.line += 1;
+            }
+
+            self.cursor.point.col = Column(0);
+            self.input_needs_wrap = false;
+        }
+
+        {
+            // Number of cells the char will occupy
+            if let Some(width) = width {
                 // Sigh, borrowck making us check the width twice. Hopefully the
                 // optimizer can fix it.
                 let num_cols = self.grid.num_cols();
@@ -1260,7 +2022,13 @@ impl ansi::Handler for Term {
 
                     let cell = &mut self.grid[&self.cursor.point];
                     *cell = self.cursor.template;
-                    cell.c = self.cursor.charsets[self.active_charset].map(c);
+                    let charset = self.cursor.single_shift.take()
+                        .unwrap_or(self.cursor.active_charset);
+                    cell.c = if self.mode.contains(mode::TermMode::VT52_GRAPHICS) {
+                        StandardCharset::SpecialCharacterAndLineDrawing.map(c)
+                    } else {
+                        self.cursor.charsets[charset].map(c)
+                    };
 
                     // Handle wide chars
                     if width == 2 {
@@ -1302,6 +2070,12 @@ impl ansi::Handler for Term {
     #[inline]
     fn goto(&mut self, line: Line, col: Column) {
         trace!("goto: line={}, col={}", line, col);
+        if self.status_active {
+            // The status line only has columns to move between
+            self.status_cursor.point.col = min(col, self.status_grid.num_cols() - 1);
+            return;
+        }
+
         let (y_offset, max_y) = if self.mode.contains(mode::TermMode::ORIGIN) {
             (self.scroll_region.start, self.scroll_region.end - 1)
         } else {
@@ -1316,7 +2090,7 @@ impl ansi::Handler for Term {
     #[inline]
     fn goto_line(&mut self, line: Line) {
         trace!("goto_line: {}", line);
-        let col = self.cursor.point.col; // borrowck
+        let col = self.active_cursor_mut().point.col; // borrowck
         self.goto(line, col)
     }
 
@@ -1358,6 +2132,10 @@ impl ansi::Handler for Term {
     #[inline]
     fn move_up(&mut self, lines: Line) {
         trace!("move_up: {}", lines);
+        if self.status_active {
+            return;
+        }
+
         let move_to = Line(self.cursor.point.line.0.saturating_sub(lines.0));
         let col = self.cursor.point.col; // borrowck
         self.goto(move_to, col)
@@ -1366,6 +2144,10 @@ impl ansi::Handler for Term {
     #[inline]
     fn move_down(&mut self, lines: Line) {
         trace!("move_down: {}", lines);
+        if self.status_active {
+            return;
+        }
+
         let move_to = self.cursor.point.line + lines;
         let col = self.cursor.point.col; // borrowck
         self.goto(move_to, col)
@@ -1374,32 +2156,97 @@ impl ansi::Handler for Term {
     #[inline]
     fn move_forward(&mut self, cols: Column) {
         trace!("move_forward: {}", cols);
-        self.cursor.point.col = min(self.cursor.point.col + cols, self.grid.num_cols() - 1);
+        let num_cols = self.grid.num_cols();
+        let cursor = self.active_cursor_mut();
+        cursor.point.col = min(cursor.point.col + cols, num_cols - 1);
         self.input_needs_wrap = false;
     }
 
     #[inline]
     fn move_backward(&mut self, cols: Column) {
         trace!("move_backward: {}", cols);
-        self.cursor.point.col -= min(self.cursor.point.col, cols);
+        let cursor = self.active_cursor_mut();
+        cursor.point.col -= min(cursor.point.col, cols);
         self.input_needs_wrap = false;
     }
 
     #[inline]
     fn identify_terminal<W: io::Write>(&mut self, writer: &mut W) {
-        let _ = writer.write_all(b"\x1b[?6c");
+        if self.mode.contains(mode::TermMode::VT52) {
+            let _ = writer.write_all(b"\x1b/Z");
+            return;
+        }
+
+        // VT220 with selective erase, national replacement character sets,
+        // technical characters, ANSI color and rectangular editing
+        self.reply(writer, C1::CSI, format_args!("?62;6;9;15;22;28c"));
+    }
+
+    #[inline]
+    fn secondary_device_attributes<W: io::Write>(&mut self, writer: &mut W) {
+        let version = version_number(env!("CARGO_PKG_VERSION"));
+        self.reply(writer, C1::CSI, format_args!(">1;{};0c", version));
+    }
+
+    #[inline]
+    fn tertiary_device_attributes<W: io::Write>(&mut self, writer: &mut W) {
+        self.reply(writer, C1::DCS, format_args!("!|00000000"));
+    }
+
+    #[inline]
+    fn report_version<W: io::Write>(&mut self, writer: &mut W) {
+        self.reply(writer, C1::DCS, format_args!(">|alacritty {}", env!("CARGO_PKG_VERSION")));
+    }
+
+    #[inline]
+    fn print_screen(&mut self) {
+        trace!("print_screen");
+        let mut text = String::new();
+        for line in IndexRange::from(Line(0)..self.grid.num_lines()) {
+            let row = &self.grid[line];
+            for cell in &row[Column(0)..row.line_length()] {
+                if !cell.flags.contains(cell::Flags::WIDE_CHAR_SPACER) {
+                    text.push(cell.c);
+                    text.extend(self.zerowidth.get(cell));
+                }
+            }
+            text.push('\n');
+        }
+
+        self.printer.print(text.as_bytes());
+    }
+
+    #[inline]
+    fn start_printer_controller(&mut self) {
+        trace!("start_printer_controller");
+        self.printer.start();
+    }
+
+    #[inline]
+    fn printer_output(&mut self, bytes: &[u8]) {
+        self.printer.write(bytes);
+    }
+
+    #[inline]
+    fn stop_printer_controller(&mut self) {
+        trace!("stop_printer_controller");
+        self.printer.finish();
+    }
+
+    #[inline]
+    fn set_c1_encoding(&mut self, encoding: ansi::C1Encoding) {
+        trace!("set_c1_encoding: {:?}", encoding);
+        self.c1_encoding = encoding;
     }
 
     #[inline]
     fn device_status<W: io::Write>(&mut self, writer: &mut W, arg: usize) {
         trace!("device status: {}", arg);
         match arg {
-            5 => {
-                let _ = writer.write_all(b"\x1b[0n");
-            },
+            5 => self.reply(writer, C1::CSI, format_args!("0n")),
             6 => {
                 let pos = self.cursor.point;
-                let _ = write!(writer, "\x1b[{};{}R", pos.line + 1, pos.col + 1);
+                self.reply(writer, C1::CSI, format_args!("{};{}R", pos.line + 1, pos.col + 1));
             },
             _ => debug!("unknown device status query: {}", arg),
         };
@@ -1407,12 +2254,16 @@ impl ansi::Handler for Term {
 
     #[inline]
     fn move_down_and_cr(&mut self, lines: Line) {
-        trace!("[unimplemented] move_down_and_cr: {}", lines);
+        trace!("move_down_and_cr: {}", lines);
+        self.move_down(lines);
+        self.carriage_return();
     }
 
     #[inline]
     fn move_up_and_cr(&mut self, lines: Line) {
-        trace!("[unimplemented] move_up_and_cr: {}", lines);
+        trace!("move_up_and_cr: {}", lines);
+        self.move_up(lines);
+        self.carriage_return();
     }
 
     #[inline]
@@ -1443,8 +2294,9 @@ impl ansi::Handler for Term {
     #[inline]
     fn backspace(&mut self) {
         trace!("backspace");
-        if self.cursor.point.col > Column(0) {
-            self.cursor.point.col -= 1;
+        let cursor = self.active_cursor_mut();
+        if cursor.point.col > Column(0) {
+            cursor.point.col -= 1;
             self.input_needs_wrap = false;
         }
     }
@@ -1453,7 +2305,7 @@ impl ansi::Handler for Term {
     #[inline]
     fn carriage_return(&mut self) {
         trace!("carriage_return");
-        self.cursor.point.col = Column(0);
+        self.active_cursor_mut().point.col = Column(0);
         self.input_needs_wrap = false;
     }
 
@@ -1461,6 +2313,10 @@ impl ansi::Handler for Term {
     #[inline]
     fn linefeed(&mut self) {
         trace!("linefeed");
+        if self.status_active {
+            return;
+        }
+
         let next = self.cursor.point.line + 1;
         if next == self.scroll_region.end {
             self.scroll_up(Line(1));
@@ -1479,7 +2335,8 @@ impl ansi::Handler for Term {
 
     #[inline]
     fn substitute(&mut self) {
-        trace!("[unimplemented] substitute");
+        trace!("substitute");
+        self.input(SUBSTITUTE_CHAR);
     }
 
     /// Run LF/NL
@@ -1553,11 +2410,12 @@ impl ansi::Handler for Term {
     #[inline]
     fn erase_chars(&mut self, count: Column) {
         trace!("erase_chars: {}, {}", count, self.cursor.point.col);
-        let start = self.cursor.point.col;
-        let end = min(start + count, self.grid.num_cols() - 1);
+        let (grid, cursor) = self.active_display_mut();
+        let start = cursor.point.col;
+        let end = min(start + count, grid.num_cols() - 1);
 
-        let row = &mut self.grid[self.cursor.point.line];
-        let template = self.cursor.template; // Cleared cells have current background color set
+        let row = &mut grid[cursor.point.line];
+        let template = cursor.template; // Cleared cells have current background color set
         for c in &mut row[start..end] {
             c.reset(&template);
         }
@@ -1609,7 +2467,8 @@ impl ansi::Handler for Term {
 
     #[inline]
     fn move_forward_tabs(&mut self, count: i64) {
-        trace!("[unimplemented] move_forward_tabs: {}", count);
+        trace!("move_forward_tabs: {}", count);
+        self.put_tab(count);
     }
 
     #[inline]
@@ -1636,36 +2495,19 @@ impl ansi::Handler for Term {
         self.cursor = *source;
         self.cursor.point.line = min(self.cursor.point.line, self.grid.num_lines() - 1);
         self.cursor.point.col = min(self.cursor.point.col, self.grid.num_cols() - 1);
+        self.input_needs_wrap = false;
     }
 
     #[inline]
     fn clear_line(&mut self, mode: ansi::LineClearMode) {
         trace!("clear_line: {:?}", mode);
-        let mut template = self.cursor.template;
-        template.flags ^= template.flags;
-
-        let col =  self.cursor.point.col;
+        self.erase_line(mode, false);
+    }
 
-        match mode {
-            ansi::LineClearMode::Right => {
-                let row = &mut self.grid[self.cursor.point.line];
-                for cell in &mut row[col..] {
-                    cell.reset(&template);
-                }
-            },
-            ansi::LineClearMode::Left => {
-                let row = &mut self.grid[self.cursor.point.line];
-                for cell in &mut row[..(col + 1)] {
-                    cell.reset(&template);
-                }
-            },
-            ansi::LineClearMode::All => {
-                let row = &mut self.grid[self.cursor.point.line];
-                for cell in &mut row[..] {
-                    cell.reset(&template);
-                }
-            },
-        }
+    #[inline]
+    fn selective_clear_line(&mut self, mode: ansi::LineClearMode) {
+        trace!("selective_clear_line: {:?}", mode);
+        self.erase_line(mode, true);
     }
 
     /// Set the indexed color value
@@ -1684,6 +2526,25 @@ impl ansi::Handler for Term {
         self.color_modified[index] = false;
     }
 
+    /// Report the value of an indexed color
+    #[inline]
+    fn color_report<W: io::Write>(&mut self, writer: &mut W, index: usize) {
+        trace!("color_report[{}]", index);
+        let prefix = match index {
+            0...255 => format!("4;{};", index),
+            i if i == NamedColor::Foreground as usize => "10;".into(),
+            i if i == NamedColor::Background as usize => "11;".into(),
+            i if i == NamedColor::Cursor as usize => "12;".into(),
+            _ => return debug!("unknown color query: {}", index),
+        };
+
+        let color = self.colors[index];
+        self.reply(writer, C1::OSC, format_args!(
+            "{}rgb:{r:02x}{r:02x}/{g:02x}{g:02x}/{b:02x}{b:02x}",
+            prefix, r = color.r, g = color.g, b = color.b
+        ));
+    }
+
     /// Set the clipboard
     #[inline]
     fn set_clipboard(&mut self, string: &str)
@@ -1698,44 +2559,19 @@ impl ansi::Handler for Term {
     #[inline]
     fn clear_screen(&mut self, mode: ansi::ClearMode) {
         trace!("clear_screen: {:?}", mode);
-        let mut template = self.cursor.template;
-        template.flags ^= template.flags;
+        self.erase_screen(mode, false);
+    }
 
-        match mode {
-            ansi::ClearMode::Below => {
-                for cell in &mut self.grid[self.cursor.point.line][self.cursor.point.col..] {
-                    cell.reset(&template);
-                }
-                if self.cursor.point.line < self.grid.num_lines() - 1 {
-                    for row in &mut self.grid[(self.cursor.point.line + 1)..] {
-                        for cell in row {
-                            cell.reset(&template);
-                        }
-                    }
-                }
-            },
-            ansi::ClearMode::All => {
-                self.grid.clear(|c| c.reset(&template));
-            },
-            ansi::ClearMode::Above => {
-                // If clearing more than one line
-                if self.cursor.point.line > Line(1) {
-                    // Fully clear all lines before the current line
-                    for row in &mut self.grid[..self.cursor.point.line] {
-                        for cell in row {
-                            cell.reset(&template);
-                        }
-                    }
-                }
-                // Clear up to the current column in the current line
-                let end = min(self.cursor.point.col + 1, self.grid.num_cols());
-                for cell in &mut self.grid[self.cursor.point.line][..end] {
-                    cell.reset(&template);
-                }
-            },
-            // If scrollback is implemented, this should clear it
-            ansi::ClearMode::Saved => return
-        }
+    #[inline]
+    fn selective_clear_screen(&mut self, mode: ansi::ClearMode) {
+        trace!("selective_clear_screen: {:?}", mode);
+        self.erase_screen(mode, true);
+    }
+
+    #[inline]
+    fn set_character_protection(&mut self, protected: bool) {
+        trace!("set_character_protection: {}", protected);
+        self.cursor.template.flags.set(cell::Flags::PROTECTED, protected);
     }
 
     #[inline]
@@ -1757,6 +2593,41 @@ impl ansi::Handler for Term {
         }
     }
 
+    #[inline]
+    fn set_status_line_type(&mut self, kind: ansi::StatusLineType) {
+        trace!("set_status_line_type: {:?}", kind);
+        self.status_active = false;
+
+        let old_rows = self.status_line_rows();
+        self.status_line = kind;
+
+        let template = Cell::default();
+        self.status_grid.clear(|c| c.reset(&template));
+        self.status_cursor = Default::default();
+
+        // Showing or hiding the status line changes the main display's size
+        if self.status_line_rows() != old_rows {
+            let size = self.size_info;
+            self.resize(&size);
+            self.next_pty_size = Some(self.pty_size_info());
+        }
+        self.dirty = true;
+    }
+
+    #[inline]
+    fn select_active_display(&mut self, display: ansi::StatusDisplay) {
+        trace!("select_active_display: {:?}", display);
+        match display {
+            ansi::StatusDisplay::StatusLine
+                if self.status_line == ansi::StatusLineType::HostWritable =>
+            {
+                self.status_active = true;
+            },
+            ansi::StatusDisplay::StatusLine => (),
+            ansi::StatusDisplay::Main => self.status_active = false,
+        }
+    }
+
     // Reset all important fields in the term struct
     #[inline]
     fn reset_state(&mut self) {
@@ -1765,7 +2636,6 @@ impl ansi::Handler for Term {
         self.next_mouse_cursor = None;
         self.alt = false;
         self.cursor = Default::default();
-        self.active_charset = Default::default();
         self.mode = Default::default();
         self.font_size = self.original_font_size;
         self.next_is_urgent = None;
@@ -1774,11 +2644,38 @@ impl ansi::Handler for Term {
         self.colors = self.original_colors;
         self.color_modified = [false; color::COUNT];
         self.cursor_style = None;
+        self.c1_encoding = Default::default();
+        self.set_status_line_type(ansi::StatusLineType::None);
+    }
+
+    #[inline]
+    fn soft_reset(&mut self) {
+        trace!("soft_reset");
+        self.input_needs_wrap = false;
+
+        self.mode.insert(mode::TermMode::SHOW_CURSOR | mode::TermMode::LINE_WRAP);
+        self.mode.remove(
+            mode::TermMode::INSERT | mode::TermMode::ORIGIN | mode::TermMode::APP_CURSOR |
+            mode::TermMode::APP_KEYPAD
+        );
+
+        // The screen content and cursor position are left alone
+        let point = self.cursor.point;
+        self.cursor = Default::default();
+        self.cursor.point = point;
+
+        self.cursor_save = Default::default();
+        self.cursor_save_alt = Default::default();
+        self.scroll_region = Line(0)..self.grid.num_lines();
     }
 
     #[inline]
     fn reverse_index(&mut self) {
         trace!("reverse_index");
+        if self.status_active {
+            return;
+        }
+
         // if cursor is at the top
         if self.cursor.point.line == self.scroll_region.start {
             self.scroll_down(Line(1));
@@ -1791,24 +2688,26 @@ impl ansi::Handler for Term {
     #[inline]
     fn terminal_attribute(&mut self, attr: Attr) {
         trace!("Set Attribute: {:?}", attr);
+        let template = &mut self.active_cursor_mut().template;
         match attr {
-            Attr::Foreground(color) => self.cursor.template.fg = color,
-            Attr::Background(color) => self.cursor.template.bg = color,
+            Attr::Foreground(color) => template.fg = color,
+            Attr::Background(color) => template.bg = color,
             Attr::Reset => {
-                self.cursor.template.fg = Color::Named(NamedColor::Foreground);
-                self.cursor.template.bg = Color::Named(NamedColor::Background);
-                self.cursor.template.flags = cell::Flags::empty();
+                template.fg = Color::Named(NamedColor::Foreground);
+                template.bg = Color::Named(NamedColor::Background);
+                // Character protection is not part of the graphic rendition
+                template.flags &= cell::Flags::PROTECTED;
             },
-            Attr::Reverse => self.cursor.template.flags.insert(cell::Flags::INVERSE),
-            Attr::CancelReverse => self.cursor.template.flags.remove(cell::Flags::INVERSE),
-            Attr::Bold => self.cursor.template.flags.insert(cell::Flags::BOLD),
-            Attr::CancelBold => self.cursor.template.flags.remove(cell::Flags::BOLD),
-            Attr::Dim => self.cursor.template.flags.insert(cell::Flags::DIM),
-            Attr::CancelBoldDim => self.cursor.template.flags.remove(cell::Flags::BOLD | cell::Flags::DIM),
-            Attr::Italic => self.cursor.template.flags.insert(cell::Flags::ITALIC),
-            Attr::CancelItalic => self.cursor.template.flags.remove(cell::Flags::ITALIC),
-            Attr::Underscore => self.cursor.template.flags.insert(cell::Flags::UNDERLINE),
-            Attr::CancelUnderline => self.cursor.template.flags.remove(cell::Flags::UNDERLINE),
+            Attr::Reverse => template.flags.insert(cell::Flags::INVERSE),
+            Attr::CancelReverse => template.flags.remove(cell::Flags::INVERSE),
+            Attr::Bold => template.flags.insert(cell::Flags::BOLD),
+            Attr::CancelBold => template.flags.remove(cell::Flags::BOLD),
+            Attr::Dim => template.flags.insert(cell::Flags::DIM),
+            Attr::CancelBoldDim => template.flags.remove(cell::Flags::BOLD | cell::Flags::DIM),
+            Attr::Italic => template.flags.insert(cell::Flags::ITALIC),
+            Attr::CancelItalic => template.flags.remove(cell::Flags::ITALIC),
+            Attr::Underscore => template.flags.insert(cell::Flags::UNDERLINE),
+            Attr::CancelUnderline => template.flags.remove(cell::Flags::UNDERLINE),
             _ => {
                 debug!("Term got unhandled attr: {:?}", attr);
             }
@@ -1820,15 +2719,18 @@ impl ansi::Handler for Term {
         trace!("set_mode: {:?}", mode);
         match mode {
             ansi::Mode::SwapScreenAndSetRestoreCursor => {
-                self.mode.insert(mode::TermMode::ALT_SCREEN);
                 self.save_cursor_position();
-                if !self.alt {
-                    self.swap_alt();
-                }
+                self.enter_alt_screen(true);
                 self.save_cursor_position();
             },
+            ansi::Mode::SwapScreen | ansi::Mode::SwapScreenAndClear => self.enter_alt_screen(false),
+            ansi::Mode::SetRestoreCursor => self.save_cursor_position(),
             ansi::Mode::ShowCursor => self.mode.insert(mode::TermMode::SHOW_CURSOR),
+            ansi::Mode::ReverseVideo => self.mode.insert(mode::TermMode::REVERSE_VIDEO),
             ansi::Mode::CursorKeys => self.mode.insert(mode::TermMode::APP_CURSOR),
+            ansi::Mode::Ansi => {
+                self.mode.remove(mode::TermMode::VT52 | mode::TermMode::VT52_GRAPHICS);
+            },
             ansi::Mode::ReportMouseClicks => {
                 self.mode.insert(mode::TermMode::MOUSE_REPORT_CLICK);
                 self.set_mouse_cursor(MouseCursor::Arrow);
@@ -1849,6 +2751,7 @@ impl ansi::Handler for Term {
             ansi::Mode::Origin => self.mode.insert(mode::TermMode::ORIGIN),
             ansi::Mode::DECCOLM => self.deccolm(),
             ansi::Mode::Insert => self.mode.insert(mode::TermMode::INSERT), // heh
+            ansi::Mode::ExplicitBidi => self.mode.insert(mode::TermMode::EXPLICIT_BIDI),
             _ => {
                 trace!(".. ignoring set_mode");
             }
@@ -1860,15 +2763,17 @@ impl ansi::Handler for Term {
         trace!("unset_mode: {:?}", mode);
         match mode {
             ansi::Mode::SwapScreenAndSetRestoreCursor => {
-                self.mode.remove(mode::TermMode::ALT_SCREEN);
                 self.restore_cursor_position();
-                if self.alt {
-                    self.swap_alt();
-                }
+                self.exit_alt_screen(false);
                 self.restore_cursor_position();
             },
+            ansi::Mode::SwapScreen => self.exit_alt_screen(false),
+            ansi::Mode::SwapScreenAndClear => self.exit_alt_screen(true),
+            ansi::Mode::SetRestoreCursor => self.restore_cursor_position(),
             ansi::Mode::ShowCursor => self.mode.remove(mode::TermMode::SHOW_CURSOR),
+            ansi::Mode::ReverseVideo => self.mode.remove(mode::TermMode::REVERSE_VIDEO),
             ansi::Mode::CursorKeys => self.mode.remove(mode::TermMode::APP_CURSOR),
+            ansi::Mode::Ansi => self.mode.insert(mode::TermMode::VT52),
             ansi::Mode::ReportMouseClicks => {
                 self.mode.remove(mode::TermMode::MOUSE_REPORT_CLICK);
                 self.set_mouse_cursor(MouseCursor::Text);
@@ -1889,6 +2794,7 @@ impl ansi::Handler for Term {
             ansi::Mode::Origin => self.mode.remove(mode::TermMode::ORIGIN),
             ansi::Mode::DECCOLM => self.deccolm(),
             ansi::Mode::Insert => self.mode.remove(mode::TermMode::INSERT),
+            ansi::Mode::ExplicitBidi => self.mode.remove(mode::TermMode::EXPLICIT_BIDI),
             _ => {
                 trace!(".. ignoring unset_mode");
             }
@@ -1921,10 +2827,22 @@ impl ansi::Handler for Term {
         self.cursor.charsets[index] = charset;
     }
 
+    #[inline]
+    fn set_vt52_graphics(&mut self, graphics: bool) {
+        trace!("set_vt52_graphics: {}", graphics);
+        self.mode.set(mode::TermMode::VT52_GRAPHICS, graphics);
+    }
+
     #[inline]
     fn set_active_charset(&mut self, index: CharsetIndex) {
         trace!("Activate {:?} character set", index);
-        self.active_charset = index;
+        self.cursor.active_charset = index;
+    }
+
+    #[inline]
+    fn single_shift(&mut self, index: CharsetIndex) {
+        trace!("Single shift {:?} character set", index);
+        self.cursor.single_shift = Some(index);
     }
 
     #[inline]
@@ -1932,20 +2850,144 @@ impl ansi::Handler for Term {
         trace!("set_cursor_style {:?}", style);
         self.cursor_style = style;
     }
+
+    #[inline]
+    fn copy_rectangle(&mut self, area: ansi::Rectangle, destination: Point) {
+        trace!("copy_rectangle: {:?} to {:?}", area, destination);
+        let (lines, cols) = match self.rectangle_region(area) {
+            Some(region) => region,
+            None => return,
+        };
+
+        let (y_offset, max_y) = if self.mode.contains(mode::TermMode::ORIGIN) {
+            (self.scroll_region.start, self.scroll_region.end - 1)
+        } else {
+            (Line(0), self.grid.num_lines() - 1)
+        };
+        let num_cols = self.grid.num_cols();
+        let dst_line = destination.line + y_offset;
+        let dst_col = destination.col;
+        if dst_line > max_y || dst_col >= num_cols {
+            return;
+        }
+
+        // Source and destination may overlap, so take a copy of the source first
+        let rows = IndexRange::from(lines)
+            .map(|line| self.grid[line][cols.clone()].to_vec())
+            .collect::<Vec<_>>();
+
+        let width = min(cols.end - cols.start, num_cols - dst_col);
+        for (i, row) in rows.iter().enumerate() {
+            let line = dst_line + i;
+            if line > max_y {
+                break;
+            }
+
+            self.grid[line][dst_col..(dst_col + width)].copy_from_slice(&row[..width.0]);
+        }
+    }
+
+    #[inline]
+    fn fill_rectangle(&mut self, c: char, area: ansi::Rectangle) {
+        trace!("fill_rectangle: {:?} with {:?}", area, c);
+        if let Some(region) = self.rectangle_region(area) {
+            let template = self.cursor.template;
+            self.grid.clear_region(region, |cell| {
+                cell.reset(&template);
+                cell.c = c;
+            });
+        }
+    }
+
+    #[inline]
+    fn erase_rectangle(&mut self, area: ansi::Rectangle) {
+        trace!("erase_rectangle: {:?}", area);
+        if let Some(region) = self.rectangle_region(area) {
+            let mut template = self.cursor.template;
+            template.flags ^= template.flags;
+
+            self.grid.clear_region(region, |cell| cell.reset(&template));
+        }
+    }
+
+    #[inline]
+    fn change_rectangle_attribute(&mut self, area: ansi::Rectangle, attr: Attr) {
+        trace!("change_rectangle_attribute: {:?} {:?}", area, attr);
+        let region = match self.rectangle_region(area) {
+            Some(region) => region,
+            None => return,
+        };
+
+        let (insert, remove) = match attr {
+            Attr::Reset => {
+                (cell::Flags::empty(), cell::Flags::BOLD | cell::Flags::UNDERLINE | cell::Flags::INVERSE)
+            },
+            Attr::Bold => (cell::Flags::BOLD, cell::Flags::empty()),
+            Attr::Underscore => (cell::Flags::UNDERLINE, cell::Flags::empty()),
+            Attr::Reverse => (cell::Flags::INVERSE, cell::Flags::empty()),
+            Attr::CancelBold => (cell::Flags::empty(), cell::Flags::BOLD),
+            Attr::CancelUnderline => (cell::Flags::empty(), cell::Flags::UNDERLINE),
+            Attr::CancelReverse => (cell::Flags::empty(), cell::Flags::INVERSE),
+            _ => {
+                debug!("Term got unhandled rectangle attr: {:?}", attr);
+                return;
+            }
+        };
+
+        self.grid.clear_region(region, |cell| {
+            cell.flags.insert(insert);
+            cell.flags.remove(remove);
+        });
+    }
+
+    #[inline]
+    fn reverse_rectangle_attribute(&mut self, area: ansi::Rectangle, attr: Attr) {
+        trace!("reverse_rectangle_attribute: {:?} {:?}", area, attr);
+        let region = match self.rectangle_region(area) {
+            Some(region) => region,
+            None => return,
+        };
+
+        let flags = match attr {
+            Attr::Reset => cell::Flags::BOLD | cell::Flags::UNDERLINE | cell::Flags::INVERSE,
+            Attr::Bold => cell::Flags::BOLD,
+            Attr::Underscore => cell::Flags::UNDERLINE,
+            Attr::Reverse => cell::Flags::INVERSE,
+            _ => {
+                debug!("Term got unhandled rectangle attr: {:?}", attr);
+                return;
+            }
+        };
+
+        self.grid.clear_region(region, |cell| cell.flags.toggle(flags));
+    }
 }
 
 #[cfg(test)]
 mod tests {
     extern crate serde_json;
 
-    use super::{Cell, Term, SizeInfo};
+    use super::{mode, Cell, Term, SizeInfo};
     use term::cell;
 
     use grid::Grid;
-    use index::{Point, Line, Column};
-    use ansi::{Handler, CharsetIndex, StandardCharset};
+    use index::{Point, Line, Column, Side};
+    use ansi::{self, Handler, CharsetIndex, NamedColor, StandardCharset};
     use selection::Selection;
     use std::mem;
+    use Rgb;
+
+    /// Size of a terminal with 7 columns and 17 lines
+    fn test_size() -> SizeInfo {
+        SizeInfo {
+            width: 21.0,
+            height: 51.0,
+            cell_width: 3.0,
+            cell_height: 3.0,
+            padding_x: 0.0,
+            padding_y: 0.0,
+        }
+    }
 
     #[test]
     fn semantic_selection_works() {
@@ -2051,6 +3093,346 @@ mod tests {
 
         assert_eq!(term.grid()[&cursor].c, '▒');
     }
+
+    #[test]
+    fn single_shift_applies_to_next_character() {
+        let size = test_size();
+        let mut term = Term::new(&Default::default(), size);
+        term.configure_charset(CharsetIndex::G2, StandardCharset::German);
+        term.configure_charset(CharsetIndex::G3, StandardCharset::DecTechnical);
+
+        term.single_shift(CharsetIndex::G2);
+        term.input('{');
+        term.input('{');
+        term.set_active_charset(CharsetIndex::G3);
+        term.input('a');
+
+        assert_eq!(term.grid()[Line(0)][Column(0)].c, 'ä');
+        assert_eq!(term.grid()[Line(0)][Column(1)].c, '{');
+        assert_eq!(term.grid()[Line(0)][Column(2)].c, 'α');
+    }
+
+    #[test]
+    fn restore_cursor_restores_charsets() {
+        let size = test_size();
+        let mut term = Term::new(&Default::default(), size);
+        term.configure_charset(CharsetIndex::G1, StandardCharset::British);
+        term.set_active_charset(CharsetIndex::G1);
+        term.save_cursor_position();
+
+        term.configure_charset(CharsetIndex::G1, StandardCharset::Ascii);
+        term.set_active_charset(CharsetIndex::G0);
+        term.restore_cursor_position();
+        term.input('#');
+
+        assert_eq!(term.grid()[Line(0)][Column(0)].c, '£');
+    }
+
+    #[test]
+    fn input_combining_characters() {
+        let size = test_size();
+        let mut term = Term::new(&Default::default(), size);
+        for c in "e\u{301}x\u{1f469}\u{200d}\u{1f467}".chars() {
+            term.input(c);
+        }
+
+        assert_eq!(term.grid()[Line(0)][Column(0)].c, 'e');
+        assert_eq!(term.grid()[Line(0)][Column(1)].c, 'x');
+        assert_eq!(term.grid()[Line(0)][Column(2)].c, '\u{1f469}');
+        assert_eq!(term.grid()[Line(0)][Column(4)].c, ' ');
+        assert_eq!(term.cursor().point.col, Column(4));
+
+        let selection = Selection::lines(Point { line: Line(0), col: Column(0) });
+        let span = selection.to_span(&term).unwrap();
+        assert_eq!(term.string_from_selection(&span), "e\u{301}x\u{1f469}\u{200d}\u{1f467}\n");
+    }
+
+    #[test]
+    fn input_ambiguous_width_character() {
+        let size = test_size();
+        let mut term = Term::new(&Default::default(), size);
+        term.input('○');
+        assert_eq!(term.cursor().point.col, Column(1));
+
+        term.ambiguous_wide = true;
+        term.input('○');
+        assert!(term.grid()[Line(0)][Column(1)].flags.contains(cell::Flags::WIDE_CHAR));
+        assert_eq!(term.cursor().point.col, Column(3));
+    }
+
+    #[test]
+    fn input_presentation_selectors() {
+        let size = test_size();
+        let mut term = Term::new(&Default::default(), size);
+        term.presentation_selectors = true;
+
+        // Text style character switched to emoji presentation
+        term.input('\u{263a}');
+        term.input('\u{fe0f}');
+        assert!(term.grid()[Line(0)][Column(0)].flags.contains(cell::Flags::WIDE_CHAR));
+        assert!(term.grid()[Line(0)][Column(1)].flags.contains(cell::Flags::WIDE_CHAR_SPACER));
+        assert_eq!(term.cursor().point.col, Column(2));
+
+        // Emoji style character switched to text presentation
+        term.input('\u{1f600}');
+        term.input('\u{fe0e}');
+        assert!(!term.grid()[Line(0)][Column(2)].flags.contains(cell::Flags::WIDE_CHAR));
+        assert_eq!(term.cursor().point.col, Column(3));
+
+        let selection = Selection::lines(Point { line: Line(0), col: Column(0) });
+        let span = selection.to_span(&term).unwrap();
+        assert_eq!(term.string_from_selection(&span), "\u{263a}\u{fe0f}\u{1f600}\u{fe0e}\n");
+    }
+
+    #[test]
+    fn substitute_cancels_sequence() {
+        let size = test_size();
+        let mut term = Term::new(&Default::default(), size);
+        let mut parser = ansi::Processor::new();
+        for byte in b"\x1b[1\x1amx" {
+            parser.advance(&mut term, *byte, &mut ::std::io::sink());
+        }
+
+        assert_eq!(term.grid()[Line(0)][Column(0)].c, '\u{2426}');
+        assert_eq!(term.grid()[Line(0)][Column(1)].c, 'm');
+        assert!(!term.grid()[Line(0)][Column(1)].flags.contains(cell::Flags::BOLD));
+        assert_eq!(term.grid()[Line(0)][Column(2)].c, 'x');
+    }
+
+    #[test]
+    fn version_number_from_crate_version() {
+        assert_eq!(super::version_number("0.1.0"), 100);
+        assert_eq!(super::version_number("1.12.3-dev"), 11203);
+    }
+
+    #[test]
+    fn report_device_attributes() {
+        let size = test_size();
+        let mut term = Term::new(&Default::default(), size);
+        let mut parser = ansi::Processor::new();
+        let mut reply = Vec::new();
+        for byte in b"\x1b[>c\x1b[>q" {
+            parser.advance(&mut term, *byte, &mut reply);
+        }
+
+        let expected = format!(
+            "\x1b[>1;{};0c\x1bP>|alacritty {}\x1b\\",
+            super::version_number(env!("CARGO_PKG_VERSION")),
+            env!("CARGO_PKG_VERSION")
+        );
+        assert_eq!(String::from_utf8(reply).unwrap(), expected);
+    }
+
+    #[test]
+    fn replies_use_selected_c1_encoding() {
+        let size = test_size();
+        let mut term = Term::new(&Default::default(), size);
+        term.colors[1usize] = Rgb { r: 0xab, g: 0x01, b: 0xff };
+        let mut parser = ansi::Processor::new();
+        let mut reply = Vec::new();
+        for byte in b"\x1b[5n\x1b G\x1b[6n\x1b]4;1;?\x07\x1b F\x1b]4;1;?\x07" {
+            parser.advance(&mut term, *byte, &mut reply);
+        }
+
+        let expected = "\x1b[0n\u{9b}1;1R\u{9d}4;1;rgb:abab/0101/ffff\u{9c}\
+                        \x1b]4;1;rgb:abab/0101/ffff\x1b\\";
+        assert_eq!(String::from_utf8(reply).unwrap(), expected);
+    }
+
+    #[test]
+    fn vt52_mode() {
+        let size = SizeInfo { width: 30.0, ..test_size() };
+        let mut term = Term::new(&Default::default(), size);
+        let mut parser = ansi::Processor::new();
+        let mut reply = Vec::new();
+        for byte in b"\x1b[?2l\x1bY\x22\x25x\x1bAy\x1bFq\x1bG\x1bZ\x1b[c\x1bFq" {
+            parser.advance(&mut term, *byte, &mut reply);
+        }
+
+        assert!(term.mode().contains(mode::TermMode::VT52));
+        assert_eq!(term.grid()[Line(2)][Column(5)].c, 'x');
+        assert_eq!(term.grid()[Line(1)][Column(6)].c, 'y');
+        assert_eq!(term.grid()[Line(1)][Column(7)].c, '─');
+        assert_eq!(term.grid()[Line(1)][Column(8)].c, '─');
+        assert_eq!(reply, b"\x1b/Z");
+
+        reply.clear();
+        for byte in b"\x1b<q\x1b[c" {
+            parser.advance(&mut term, *byte, &mut reply);
+        }
+
+        // Leaving VT52 mode ends its graphics mode, the G0 set is still ASCII
+        assert!(!term.mode().contains(mode::TermMode::VT52));
+        assert_eq!(term.grid()[Line(1)][Column(9)].c, 'q');
+        assert_eq!(reply, b"\x1b[?62;6;9;15;22;28c");
+    }
+
+    #[test]
+    fn host_writable_status_line() {
+        let size = test_size();
+        let mut term = Term::new(&Default::default(), size);
+        let mut parser = ansi::Processor::new();
+        for byte in b"a\x1b[2$~\x1b[1$}status\x1b[0$}b" {
+            parser.advance(&mut term, *byte, &mut ::std::io::sink());
+        }
+
+        assert_eq!(term.status_line_rows(), 1);
+        assert_eq!(term.grid().num_lines(), Line(16));
+        assert_eq!(term.pty_size_info().lines(), Line(16));
+        assert_eq!(term.get_next_pty_size().map(|size| size.lines()), Some(Line(16)));
+        assert!(term.get_next_pty_size().is_none());
+        assert_eq!(term.grid()[Line(0)][Column(0)].c, 'a');
+        assert_eq!(term.grid()[Line(0)][Column(1)].c, 'b');
+
+        let config = Default::default();
+        let cells = term.renderable_status_line(&config, true)
+            .unwrap()
+            .collect::<Vec<_>>();
+        assert!(cells.iter().all(|cell| cell.line == Line(16)));
+        let text = cells.iter().map(|cell| cell.c).filter(|c| *c != ' ').collect::<String>();
+        assert_eq!(text, "status");
+
+        for byte in b"\x1b[0$~" {
+            parser.advance(&mut term, *byte, &mut ::std::io::sink());
+        }
+
+        assert_eq!(term.status_line_rows(), 0);
+        assert_eq!(term.grid().num_lines(), Line(17));
+        assert_eq!(term.get_next_pty_size().map(|size| size.lines()), Some(Line(17)));
+        assert!(term.renderable_status_line(&config, true).is_none());
+    }
+
+    #[test]
+    fn wide_char_on_narrow_status_line() {
+        let size = SizeInfo { width: 3.0, ..test_size() };
+        let mut term = Term::new(&Default::default(), size);
+        let mut parser = ansi::Processor::new();
+        for byte in "\x1b[2$~\x1b[1$}日日".as_bytes() {
+            parser.advance(&mut term, *byte, &mut ::std::io::sink());
+        }
+
+        assert_eq!(term.status_grid.num_cols(), Column(2));
+        assert_eq!(term.status_grid[Line(0)][Column(0)].c, '日');
+        assert!(term.status_grid[Line(0)][Column(1)].flags.contains(cell::Flags::WIDE_CHAR_SPACER));
+    }
+
+    #[test]
+    fn status_line_output_is_routed() {
+        let size = test_size();
+        let mut term = Term::new(&Default::default(), size);
+        let mut parser = ansi::Processor::new();
+        for byte in b"\x1b[2$~\x1b[1$}abcdefghi\r\x1b[3GX\x1b[K\n\x1b[5;5H" {
+            parser.advance(&mut term, *byte, &mut ::std::io::sink());
+        }
+
+        // The main display keeps its size and contents
+        assert_eq!(term.grid().num_lines(), Line(16));
+        assert_eq!(term.cursor().point, Point::new(Line(0), Column(0)));
+        assert!(term.grid()[Line(0)].iter().all(|cell| cell.c == ' '));
+
+        // Characters past the end of the status line overwrite the last column
+        let text = term.status_grid[Line(0)].iter().map(|cell| cell.c).collect::<String>();
+        assert_eq!(text, "abX    ");
+        assert_eq!(term.status_cursor.point, Point::new(Line(0), Column(4)));
+
+        // Resizing doesn't end status line output
+        let size = SizeInfo { width: 30.0, ..size };
+        term.resize(&size);
+        for byte in b"Y" {
+            parser.advance(&mut term, *byte, &mut ::std::io::sink());
+        }
+
+        assert_eq!(term.status_grid[Line(0)][Column(4)].c, 'Y');
+        assert_eq!(term.grid()[Line(0)][Column(0)].c, ' ');
+    }
+
+    #[test]
+    fn reverse_video_swaps_default_colors() {
+        let size = test_size();
+        let mut term = Term::new(&Default::default(), size);
+        term.input('a');
+        term.set_mode(ansi::Mode::ReverseVideo);
+
+        let config = Default::default();
+        let cell = term.renderable_cells(&config, None, true)
+            .find(|cell| cell.c == 'a')
+            .unwrap();
+
+        assert_eq!(cell.fg, term.colors[NamedColor::Background]);
+        assert_eq!(cell.bg, term.colors[NamedColor::Foreground]);
+        assert_eq!(term.background_color(), term.colors[NamedColor::Foreground]);
+    }
+
+    #[test]
+    fn restore_cursor_after_last_column() {
+        let size = test_size();
+        let mut term = Term::new(&Default::default(), size);
+        let mut parser = ansi::Processor::new();
+
+        // Writing the last column leaves a wrap pending, which the restored
+        // cursor must not inherit
+        for byte in b"\x1b[3;2H\x1b7\x1b[1;7HX\x1b8A" {
+            parser.advance(&mut term, *byte, &mut ::std::io::sink());
+        }
+
+        assert_eq!(term.grid()[Line(2)][Column(1)].c, 'A');
+        assert_eq!(term.grid()[Line(3)][Column(0)].c, ' ');
+    }
+
+    #[test]
+    fn clear_above_from_second_line() {
+        let size = test_size();
+        let mut term = Term::new(&Default::default(), size);
+        let mut parser = ansi::Processor::new();
+        for byte in b"ab\r\ncd\x1b[1J" {
+            parser.advance(&mut term, *byte, &mut ::std::io::sink());
+        }
+
+        // The first line is above the cursor too
+        assert_eq!(term.grid()[Line(0)][Column(0)].c, ' ');
+        assert_eq!(term.grid()[Line(0)][Column(1)].c, ' ');
+        assert_eq!(term.grid()[Line(1)][Column(1)].c, ' ');
+    }
+
+    #[test]
+    fn soft_reset_keeps_screen() {
+        let size = test_size();
+        let mut term = Term::new(&Default::default(), size);
+        let mut parser = ansi::Processor::new();
+        for byte in b"\x1b[2;5r\x1b[?6h\x1b[4h\x1b[1m\x1b(0ab\x1b[!pq" {
+            parser.advance(&mut term, *byte, &mut ::std::io::sink());
+        }
+
+        assert_eq!(term.grid()[Line(0)][Column(0)].c, '▒');
+        assert_eq!(term.grid()[Line(0)][Column(1)].c, '\u{2409}');
+        assert_eq!(term.grid()[Line(0)][Column(2)].c, 'q');
+        assert!(!term.grid()[Line(0)][Column(2)].flags.contains(cell::Flags::BOLD));
+        assert!(!term.mode().intersects(mode::TermMode::ORIGIN | mode::TermMode::INSERT));
+        assert_eq!(term.scroll_region, Line(0)..Line(17));
+    }
+
+    #[test]
+    fn bidi_mouse_coords() {
+        let size = test_size();
+        let mut term = Term::new(&Default::default(), size);
+        term.bidi = true;
+        for c in "ab\u{5d0}\u{5d1}\u{5d2}".chars() {
+            term.input(c);
+        }
+
+        // Mouse coordinates are screen positions, reported as is
+        let point = |col| Point { line: Line(0), col: Column(col) };
+        assert_eq!(term.pixels_to_coords(7, 1), Some(point(2)));
+
+        // Hebrew letters are displayed right to left, mirroring their sides
+        assert_eq!(term.selection_point(point(2), Side::Left), (point(4), Side::Right));
+        assert_eq!(term.selection_point(point(4), Side::Right), (point(2), Side::Left));
+        assert_eq!(term.selection_point(point(0), Side::Left), (point(0), Side::Left));
+
+        // Applications doing their own reordering get the screen position
+        term.set_mode(ansi::Mode::ExplicitBidi);
+        assert_eq!(term.selection_point(point(2), Side::Left), (point(2), Side::Left));
+    }
 }
 
 #[cfg(all(test, feature = "bench"))]
@@ -0,0 +1,320 @@
+// Copyright 2016 Joe Wilm, The Alacritty Project Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+//! Media copy to a printer
+//!
+//! There is no real printer; print jobs are appended to a file or piped into
+//! the standard input of a command, as configured.
+use std::borrow::Cow;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, Sender};
+
+use config::PrinterConfig;
+use encoding::Encoding;
+use util::thread::{self, JoinHandle};
+
+/// Amount of print data collected before it's sent to the printer thread
+const MAX_PENDING: usize = 0x1000;
+
+/// Destination of print jobs
+///
+/// Print data is only queued here; files and commands are opened and written
+/// by a thread of their own, so a slow printer never blocks the parser.
+#[derive(Default)]
+pub struct Printer {
+    /// File print jobs are appended to
+    file: Option<PathBuf>,
+
+    /// Command started for every print job
+    command: Option<(String, Vec<String>)>,
+
+    /// Encoding print jobs are sent in, the same as the pty's
+    encoding: Encoding,
+
+    /// Whether a print job is in progress
+    printing: bool,
+
+    /// Data of the current job not yet sent to the printer thread
+    pending: Vec<u8>,
+
+    /// Requests for the printer thread, which is started with the first job
+    sender: Option<Sender<Request>>,
+
+    /// Thread writing the print jobs
+    thread: Option<JoinHandle<()>>,
+}
+
+/// Request handled by the printer thread
+enum Request {
+    /// Start a new print job, finishing the current one
+    Start(Destination),
+
+    /// Add data to the current print job
+    Write(Vec<u8>),
+
+    /// Complete the current print job
+    Finish,
+}
+
+enum Destination {
+    File(PathBuf),
+    Command(String, Vec<String>),
+}
+
+enum Job {
+    File(BufWriter<File>),
+    Command(Child, BufWriter<ChildStdin>),
+}
+
+impl Printer {
+    pub fn new(config: &PrinterConfig) -> Printer {
+        let mut printer = Printer::default();
+        printer.update_config(config);
+        printer
+    }
+
+    /// Change the destination of future print jobs
+    pub fn update_config(&mut self, config: &PrinterConfig) {
+        self.file = config.file().map(PathBuf::from);
+        self.command = config.command()
+            .map(|shell| (shell.program().to_owned(), shell.args().to_vec()));
+    }
+
+    /// Change the encoding of future print data
+    pub fn set_encoding(&mut self, encoding: Encoding) {
+        self.encoding = encoding;
+    }
+
+    /// Start a new print job, finishing the current one
+    pub fn start(&mut self) {
+        self.finish();
+
+        let destination = if let Some((ref program, ref args)) = self.command {
+            Destination::Command(program.clone(), args.clone())
+        } else if let Some(ref path) = self.file {
+            Destination::File(path.clone())
+        } else {
+            debug!("no printer configured; discarding print job");
+            return;
+        };
+
+        self.send(Request::Start(destination));
+        self.printing = true;
+    }
+
+    /// Add data to the current print job
+    ///
+    /// Printer controller data arrives a byte at a time, so it is collected
+    /// and sent in chunks which don't split any characters.
+    pub fn write(&mut self, bytes: &[u8]) {
+        if !self.printing {
+            return;
+        }
+
+        self.pending.extend_from_slice(bytes);
+        if self.pending.len() >= MAX_PENDING {
+            // Keep the last character, which may not be complete yet
+            let end = match self.pending.iter().rposition(|byte| byte & 0xc0 != 0x80) {
+                Some(0) | None => self.pending.len(),
+                Some(start) => start,
+            };
+            self.flush(end);
+        }
+    }
+
+    /// Complete the current print job
+    pub fn finish(&mut self) {
+        if self.printing {
+            let end = self.pending.len();
+            self.flush(end);
+            self.send(Request::Finish);
+            self.printing = false;
+        }
+    }
+
+    /// Print `bytes` as a job of its own
+    pub fn print(&mut self, bytes: &[u8]) {
+        self.start();
+        self.write(bytes);
+        self.finish();
+    }
+
+    /// Send the first `end` pending bytes to the printer thread
+    fn flush(&mut self, end: usize) {
+        if end == 0 {
+            return;
+        }
+
+        let bytes = self.encoding.encode(Cow::Borrowed(&self.pending[..end])).into_owned();
+        self.pending.drain(..end);
+        self.send(Request::Write(bytes));
+    }
+
+    fn send(&mut self, request: Request) {
+        if self.sender.is_none() {
+            let (sender, receiver) = mpsc::channel();
+            self.thread = Some(thread::spawn_named("printer", move || run(&receiver)));
+            self.sender = Some(sender);
+        }
+
+        if let Some(ref sender) = self.sender {
+            // The thread only exits once the sender is dropped
+            let _ = sender.send(request);
+        }
+    }
+}
+
+impl Drop for Printer {
+    fn drop(&mut self) {
+        self.finish();
+    }
+}
+
+/// Handle print requests until the `Printer` is dropped
+fn run(requests: &Receiver<Request>) {
+    let mut job = None;
+    for request in requests {
+        match request {
+            Request::Start(destination) => {
+                finish(job.take());
+                job = match open(&destination) {
+                    Ok(job) => Some(job),
+                    Err(err) => {
+                        warn!("couldn't start print job: {}", err);
+                        None
+                    },
+                };
+            },
+            Request::Write(bytes) => {
+                let result = match job {
+                    Some(Job::File(ref mut writer)) => writer.write_all(&bytes),
+                    Some(Job::Command(_, ref mut writer)) => writer.write_all(&bytes),
+                    None => continue,
+                };
+
+                if let Err(err) = result {
+                    warn!("print job failed: {}", err);
+                    job = None;
+                }
+            },
+            Request::Finish => finish(job.take()),
+        }
+    }
+
+    finish(job);
+}
+
+fn open(destination: &Destination) -> io::Result<Job> {
+    match *destination {
+        Destination::Command(ref program, ref args) => {
+            let mut child = Command::new(program)
+                .args(args)
+                .stdin(Stdio::piped())
+                .spawn()?;
+            let stdin = child.stdin.take().unwrap();
+            Ok(Job::Command(child, BufWriter::new(stdin)))
+        },
+        Destination::File(ref path) => {
+            let file = OpenOptions::new().create(true).append(true).open(path)?;
+            Ok(Job::File(BufWriter::new(file)))
+        },
+    }
+}
+
+fn finish(job: Option<Job>) {
+    match job {
+        Some(Job::File(mut writer)) => {
+            if let Err(err) = writer.flush() {
+                warn!("print job failed: {}", err);
+            }
+        },
+        Some(Job::Command(mut child, mut writer)) => {
+            if let Err(err) = writer.flush() {
+                warn!("print job failed: {}", err);
+            }
+
+            // Closing stdin ends the job; jobs are printed one at a time
+            drop(writer);
+            let _ = child.wait();
+        },
+        None => (),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs::{self, File};
+    use std::io::Read;
+    use std::process;
+
+    use encoding::Encoding;
+
+    use super::Printer;
+
+    /// Drop the printer and wait until its jobs are written
+    fn wait(mut printer: Printer) {
+        printer.sender.take();
+        printer.thread.take().unwrap().join().unwrap();
+    }
+
+    #[test]
+    fn print_jobs_are_appended_to_file() {
+        let path = env::temp_dir().join(format!("alacritty-printer-{}", process::id()));
+        let _ = fs::remove_file(&path);
+
+        let mut printer = Printer::default();
+        printer.file = Some(path.clone());
+        printer.start();
+        printer.write(b"first ");
+        for byte in b"job\n" {
+            printer.write(&[*byte]);
+        }
+        printer.finish();
+        printer.write(b"discarded\n");
+        printer.print(b"second job\n");
+        wait(printer);
+
+        let mut contents = String::new();
+        File::open(&path).unwrap().read_to_string(&mut contents).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(contents, "first job\nsecond job\n");
+    }
+
+    #[test]
+    fn print_jobs_use_pty_encoding() {
+        let path = env::temp_dir().join(format!("alacritty-printer-enc-{}", process::id()));
+        let _ = fs::remove_file(&path);
+
+        let mut printer = Printer::default();
+        printer.file = Some(path.clone());
+        printer.set_encoding(Encoding::Latin1);
+        printer.start();
+        for byte in "déjà vu\n".as_bytes() {
+            printer.write(&[*byte]);
+        }
+        printer.finish();
+        wait(printer);
+
+        let mut contents = Vec::new();
+        File::open(&path).unwrap().read_to_end(&mut contents).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(contents, b"d\xe9j\xe0 vu\n");
+    }
+}
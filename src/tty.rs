@@ -16,6 +16,7 @@
 //!
 use std::ffi::CStr;
 use std::fs::File;
+use std::io::Read;
 use std::os::unix::io::FromRawFd;
 use std::os::unix::process::CommandExt;
 use std::ptr;
@@ -295,18 +296,62 @@ impl Pty {
     /// Tells the kernel that the window size changed with the new pixel
     /// dimensions and line/column counts.
     pub fn resize<T: ToWinsize>(&self, size: &T) {
-        let win = size.to_winsize();
+        resize(self.fd, size);
+    }
+
+    /// Get a handle for looking up the foreground process of the pty
+    pub fn foreground_process(&self) -> ForegroundProcess {
+        ForegroundProcess { fd: self.fd }
+    }
+}
+
+/// Looks up the process group in the foreground of a pty
+///
+/// This is what's currently running in the shell, for example `vim`, or the
+/// shell itself when it's waiting for a command.
+#[derive(Debug, Clone, Copy)]
+pub struct ForegroundProcess {
+    fd: c_int,
+}
+
+impl ForegroundProcess {
+    /// Name of the foreground process group
+    ///
+    /// Read from `/proc/<pgid>/comm`, so this is only available on systems
+    /// with a Linux-style procfs.
+    pub fn name(&self) -> Option<String> {
+        let pgid = unsafe { libc::tcgetpgrp(self.fd) };
+        if pgid < 0 {
+            return None;
+        }
 
-        let res = unsafe {
-            libc::ioctl(self.fd, libc::TIOCSWINSZ, &win as *const _)
-        };
+        let mut comm = String::new();
+        let res = File::open(format!("/proc/{}/comm", pgid))
+            .and_then(|mut file| file.read_to_string(&mut comm));
 
-        if res < 0 {
-            die!("ioctl TIOCSWINSZ failed: {}", errno());
+        match res {
+            Ok(_) => Some(comm.trim_right().to_owned()),
+            Err(_) => None,
         }
     }
 }
 
+/// Resize the pty behind `fd`
+///
+/// The I/O loop only holds a file for the pty; this lets it resize the pty
+/// without waiting for the display.
+pub fn resize<T: ToWinsize>(fd: c_int, size: &T) {
+    let win = size.to_winsize();
+
+    let res = unsafe {
+        libc::ioctl(fd, libc::TIOCSWINSZ, &win as *const _)
+    };
+
+    if res < 0 {
+        die!("ioctl TIOCSWINSZ failed: {}", errno());
+    }
+}
+
 /// Types that can produce a `libc::winsize`
 pub trait ToWinsize {
     /// Get a `libc::winsize`
@@ -27,12 +27,29 @@ macro_rules! ref_tests {
 }
 
 ref_tests! {
+    alt_screen_1047
+    alt_screen_47
+    autowrap_toggle
+    autowrap_with_controls
+    charset_graphics
+    cnl_cpl_cht
+    controls_inside_escapes
     csi_rep
+    deccara
+    deccra
+    decera
+    decfra
+    decrara
+    device_reports
     fish_cc
     indexed_256_colors
     issue_855
+    leading_zero_params
     ll
     newline_with_cursor_beyond_scroll_region
+    save_cursor_1048
+    save_restore_cursor
+    selective_erase
     tab_rendering
     tmux_git_log
     tmux_htop